@@ -0,0 +1,468 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::discovery::{pnpm_overrides, pnpm_package_dirs};
+use crate::{Error, Transaction, json};
+
+/// package.json fields whose ranges are expected to agree across the workspace.
+///
+/// `peerDependencies` are left out: they state what a package is compatible with,
+/// which is often deliberately wider than what the workspace installs.
+const ALIGNED_FIELDS: [&str; 3] = ["dependencies", "devDependencies", "optionalDependencies"];
+
+/// A dependency requested with more than one range across the workspace.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DependencyMismatch {
+    /// Name of the dependency
+    pub name: String,
+    /// Every place the dependency is requested, sorted by path
+    pub usages: Vec<DependencyUsage>,
+    /// The range pnpm-workspace.yaml forces through `overrides`, if any
+    pub override_range: Option<String>,
+}
+
+/// One place a dependency is requested.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DependencyUsage {
+    /// package.json that requests the dependency
+    pub path: PathBuf,
+    /// Field the dependency is listed in, such as `devDependencies`
+    pub field: String,
+    /// The requested range
+    pub range: String,
+}
+
+/// A dependency range rewritten by `align_dependencies`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlignChange {
+    /// package.json that was rewritten
+    pub path: PathBuf,
+    /// Field the dependency is listed in
+    pub field: String,
+    /// Name of the dependency
+    pub dependency: String,
+    /// Range before the change
+    pub previous_range: String,
+    /// Range after the change
+    pub new_range: String,
+}
+
+/// Chooses the range `align_dependencies` rewrites each dependency to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlignOptions {
+    /// Range to use for a dependency, by name. Every usage of a listed dependency is
+    /// rewritten to it, whether or not the usages agreed before.
+    pub ranges: BTreeMap<String, String>,
+    /// For a mismatched dependency not listed in `ranges`, use the range its
+    /// pnpm-workspace.yaml override forces, or else the range whose version is
+    /// highest. Without this, such dependencies are left alone.
+    pub highest: bool,
+}
+
+/// A dependency entry found while scanning, with where its range sits in the file.
+struct Usage {
+    file: usize,
+    field: String,
+    range: String,
+    span: Range<usize>,
+}
+
+/// The package.json files of a pnpm workspace and the dependencies they request.
+struct Scan {
+    files: Vec<(PathBuf, String)>,
+    usages: BTreeMap<String, Vec<Usage>>,
+    overrides: BTreeMap<String, String>,
+}
+
+/// Finds the dependencies requested with different ranges across the package.json
+/// files of the pnpm workspace at `root`.
+///
+/// The workspace is the root package.json plus every package matched by
+/// `root/pnpm-workspace.yaml`. The `dependencies`, `devDependencies` and
+/// `optionalDependencies` of each are compared; `workspace:` ranges point at a
+/// package of the workspace itself and are skipped. A dependency with an entry in
+/// the `overrides` of pnpm-workspace.yaml is also a mismatch when its ranges agree
+/// with each other but not with the override, since the override is hiding that
+/// drift at install time.
+///
+/// # Errors
+///
+/// Errors about a file are tied to it through `Error::InFile`.
+///
+/// * `Error::Io` - A file cannot be read
+/// * `Error::JsonSyntax` - Invalid package.json
+/// * `Error::Yaml` / `Error::Glob` - Invalid pnpm-workspace.yaml
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use package_version_sync::find_mismatches;
+///
+/// for mismatch in find_mismatches(".".as_ref())? {
+///     println!("{} is requested {} ways", mismatch.name, mismatch.usages.len());
+/// }
+/// # Ok::<(), package_version_sync::Error>(())
+/// ```
+pub fn find_mismatches(root: &Path) -> Result<Vec<DependencyMismatch>, Error> {
+    let scan = Scan::read(root)?;
+    Ok(scan
+        .usages
+        .iter()
+        .filter(|(name, usages)| scan.is_mismatched(name, usages))
+        .map(|(name, usages)| DependencyMismatch {
+            name: name.clone(),
+            usages: usages
+                .iter()
+                .map(|usage| DependencyUsage {
+                    path: scan.files[usage.file].0.clone(),
+                    field: usage.field.clone(),
+                    range: usage.range.clone(),
+                })
+                .collect(),
+            override_range: scan.overrides.get(name).cloned(),
+        })
+        .collect())
+}
+
+/// Rewrites dependency ranges across the pnpm workspace at `root` so each
+/// dependency is requested with a single range, chosen through `options`.
+///
+/// Dependencies are found as in `find_mismatches`. Each rewrite replaces only the
+/// range's text, leaving the rest of the file as it was, and every file lands
+/// together through one `Transaction`. A mismatched dependency that `options` picks
+/// no range for is left alone, and is still reported by `find_mismatches`.
+///
+/// # Errors
+///
+/// * `Error::UnknownDependency` - `options.ranges` names a dependency no
+///   package.json requests
+/// * The errors of `find_mismatches`, and of `Transaction::commit`
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use package_version_sync::{AlignOptions, align_dependencies};
+///
+/// let options = AlignOptions {
+///     ranges: [("react".to_string(), "19.2.0".to_string())].into(),
+///     highest: true,
+/// };
+/// for change in align_dependencies(".".as_ref(), options)? {
+///     println!("{}: {} -> {}", change.dependency, change.previous_range, change.new_range);
+/// }
+/// # Ok::<(), package_version_sync::Error>(())
+/// ```
+pub fn align_dependencies(root: &Path, options: AlignOptions) -> Result<Vec<AlignChange>, Error> {
+    let scan = Scan::read(root)?;
+    if let Some(name) = options
+        .ranges
+        .keys()
+        .find(|name| !scan.usages.contains_key(*name))
+    {
+        return Err(Error::UnknownDependency { name: name.clone() });
+    }
+
+    let mut changes = Vec::new();
+    let mut edits: Vec<Vec<(Range<usize>, String)>> = vec![Vec::new(); scan.files.len()];
+    for (name, usages) in &scan.usages {
+        let chosen = match options.ranges.get(name) {
+            Some(range) => range.clone(),
+            None if options.highest && scan.is_mismatched(name, usages) => {
+                match scan.highest_range(name, usages) {
+                    Some(range) => range,
+                    None => continue,
+                }
+            }
+            None => continue,
+        };
+        for usage in usages.iter().filter(|usage| usage.range != chosen) {
+            edits[usage.file].push((usage.span.clone(), json::quote(&chosen)));
+            changes.push(AlignChange {
+                path: scan.files[usage.file].0.clone(),
+                field: usage.field.clone(),
+                dependency: name.clone(),
+                previous_range: usage.range.clone(),
+                new_range: chosen.clone(),
+            });
+        }
+    }
+
+    let mut transaction = Transaction::new();
+    for ((path, contents), edits) in scan.files.iter().zip(edits) {
+        if edits.is_empty() {
+            continue;
+        }
+        let patched = json::splice(contents, edits);
+        json::parse(&patched)
+            .map_err(|error| Error::JsonSyntax(error).in_file(path, Some(&patched)))?;
+        transaction.stage(path, patched);
+    }
+    transaction.commit()?;
+
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+impl Scan {
+    fn read(root: &Path) -> Result<Self, Error> {
+        let mut files = Vec::new();
+        let mut usages: BTreeMap<String, Vec<Usage>> = BTreeMap::new();
+        for dir in pnpm_package_dirs(root)? {
+            let path = root.join(dir).join("package.json");
+            let contents =
+                fs::read_to_string(&path).map_err(|error| Error::Io(error).in_file(&path, None))?;
+            let package = json::parse(&contents)
+                .map_err(|error| Error::JsonSyntax(error).in_file(&path, Some(&contents)))?;
+
+            for field in ALIGNED_FIELDS {
+                let Some(json::NodeKind::Object(members)) =
+                    package.get(field).map(|node| &node.kind)
+                else {
+                    continue;
+                };
+                for member in members {
+                    let Some(range) = member.value.as_str() else {
+                        continue;
+                    };
+                    if range.starts_with("workspace:") {
+                        continue;
+                    }
+                    usages.entry(member.key.clone()).or_default().push(Usage {
+                        file: files.len(),
+                        field: field.to_string(),
+                        range: range.to_string(),
+                        span: member.value.span.clone(),
+                    });
+                }
+            }
+            files.push((path, contents));
+        }
+
+        Ok(Self {
+            files,
+            usages,
+            overrides: pnpm_overrides(root)?,
+        })
+    }
+
+    fn is_mismatched(&self, name: &str, usages: &[Usage]) -> bool {
+        let first = self
+            .overrides
+            .get(name)
+            .unwrap_or(&usages[0].range)
+            .as_str();
+        usages.iter().any(|usage| usage.range != first)
+    }
+
+    /// The override for `name`, or else the first of the ranges whose version is
+    /// highest. `None` when there is no override and no range names a version.
+    fn highest_range(&self, name: &str, usages: &[Usage]) -> Option<String> {
+        if let Some(range) = self.overrides.get(name) {
+            return Some(range.clone());
+        }
+        usages
+            .iter()
+            .filter_map(|usage| Some((range_version(&usage.range)?, &usage.range)))
+            .reduce(|highest, candidate| {
+                if candidate.0.cmp_precedence(&highest.0).is_gt() {
+                    candidate
+                } else {
+                    highest
+                }
+            })
+            .map(|(_, range)| range.clone())
+    }
+}
+
+/// The version a single-comparator range such as `^1.2`, `~1.2.3` or `1.2.3-beta.1`
+/// names, with missing parts taken as zero.
+fn range_version(range: &str) -> Option<semver::Version> {
+    let version = range.trim().trim_start_matches(['^', '~', '=', 'v']);
+    let core_end = version.find(['-', '+']).unwrap_or(version.len());
+    let padding = match version[..core_end].matches('.').count() {
+        0 => ".0.0",
+        1 => ".0",
+        _ => "",
+    };
+    semver::Version::parse(&format!(
+        "{}{}{}",
+        &version[..core_end],
+        padding,
+        &version[core_end..]
+    ))
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write;
+
+    /// A workspace where @types/node, @types/react and react drift apart, and
+    /// typescript agrees.
+    fn mismatched_workspace() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            "pnpm-workspace.yaml",
+            "packages:\n  - apps/*\n  - packages/*\n\noverrides:\n  \"@types/react\": \"19.2.2\"\n",
+        );
+        write(
+            root.path(),
+            "package.json",
+            "{\n  \"devDependencies\": {\n    \"typescript\": \"^5.9.3\"\n  }\n}\n",
+        );
+        write(
+            root.path(),
+            "apps/web/package.json",
+            "{\n  \"dependencies\": {\n    \"net-utils\": \"workspace:^\",\n    \"react\": \"19.2.0\"\n  },\n  \"devDependencies\": {\n    \"@types/node\": \"^20.19.24\",\n    \"@types/react\": \"^19.0.0\",\n    \"typescript\": \"^5.9.3\"\n  }\n}\n",
+        );
+        write(
+            root.path(),
+            "packages/net-utils/package.json",
+            "{\n  \"dependencies\": {\n    \"react\": \"^19.1\"\n  },\n  \"devDependencies\": {\n    \"@types/node\": \"^22.19.0\"\n  },\n  \"peerDependencies\": {\n    \"react\": \">=18\"\n  }\n}\n",
+        );
+        root
+    }
+
+    #[test]
+    fn test_find_mismatches() {
+        let root = mismatched_workspace();
+
+        let mismatches = find_mismatches(root.path()).unwrap();
+
+        let summary = mismatches
+            .iter()
+            .map(|mismatch| {
+                (
+                    mismatch.name.as_str(),
+                    mismatch
+                        .usages
+                        .iter()
+                        .map(|usage| usage.range.as_str())
+                        .collect::<Vec<_>>(),
+                    mismatch.override_range.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("@types/node", vec!["^20.19.24", "^22.19.0"], None),
+                ("@types/react", vec!["^19.0.0"], Some("19.2.2")),
+                ("react", vec!["19.2.0", "^19.1"], None),
+            ]
+        );
+        assert_eq!(
+            mismatches[0].usages[0].path,
+            root.path().join("apps/web/package.json")
+        );
+        assert_eq!(mismatches[0].usages[0].field, "devDependencies");
+    }
+
+    #[test]
+    fn test_align_dependencies_highest() {
+        let root = mismatched_workspace();
+        let options = AlignOptions {
+            highest: true,
+            ..AlignOptions::default()
+        };
+
+        let changes = align_dependencies(root.path(), options).unwrap();
+
+        let summary = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.dependency.as_str(),
+                    change.previous_range.as_str(),
+                    change.new_range.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("@types/node", "^20.19.24", "^22.19.0"),
+                ("@types/react", "^19.0.0", "19.2.2"),
+                ("react", "^19.1", "19.2.0"),
+            ]
+        );
+        assert!(find_mismatches(root.path()).unwrap().is_empty());
+        assert_eq!(
+            fs::read_to_string(root.path().join("packages/net-utils/package.json")).unwrap(),
+            "{\n  \"dependencies\": {\n    \"react\": \"19.2.0\"\n  },\n  \"devDependencies\": {\n    \"@types/node\": \"^22.19.0\"\n  },\n  \"peerDependencies\": {\n    \"react\": \">=18\"\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_align_dependencies_chosen_range() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            "pnpm-workspace.yaml",
+            "packages:\n  - apps/*\n",
+        );
+        write(
+            root.path(),
+            "package.json",
+            "{ \"devDependencies\": { \"typescript\": \"^5.9.3\" } }\n",
+        );
+        write(
+            root.path(),
+            "apps/web/package.json",
+            "{ \"devDependencies\": { \"typescript\": \"^5.9.3\" } }\n",
+        );
+        let options = AlignOptions {
+            ranges: [("typescript".to_string(), "~5.9.3".to_string())].into(),
+            highest: false,
+        };
+
+        let changes = align_dependencies(root.path(), options).unwrap();
+
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|change| change.new_range == "~5.9.3"));
+        assert_eq!(
+            fs::read_to_string(root.path().join("apps/web/package.json")).unwrap(),
+            "{ \"devDependencies\": { \"typescript\": \"~5.9.3\" } }\n"
+        );
+    }
+
+    #[test]
+    fn test_align_dependencies_unknown_dependency() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "package.json", "{}\n");
+        let options = AlignOptions {
+            ranges: [("left-pad".to_string(), "1.3.0".to_string())].into(),
+            highest: false,
+        };
+
+        let error = align_dependencies(root.path(), options).unwrap_err();
+
+        assert!(matches!(error, Error::UnknownDependency { ref name } if name == "left-pad"));
+    }
+
+    #[test]
+    fn test_range_version() {
+        let cases = [
+            ("^1.2", Some("1.2.0")),
+            ("~1.2.3", Some("1.2.3")),
+            ("=v2", Some("2.0.0")),
+            ("1.0.0-beta.1", Some("1.0.0-beta.1")),
+            ("1.x", None),
+            (">=1 <2", None),
+            ("github:owner/repo", None),
+        ];
+
+        for (range, expected) in cases {
+            assert_eq!(
+                range_version(range).map(|version| version.to_string()),
+                expected.map(str::to_string),
+                "{}",
+                range
+            );
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use crate::manifest::{Manifest, ManifestChange, parse_version};
+use crate::{CargoToml, Error, PackageJson};
+
+/// Which part of a version to advance.
+///
+/// The pre-release kinds take an optional identifier such as `beta`; without one
+/// the pre-release is a bare number, as with npm's `--preid`-less `npm version`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BumpKind {
+    /// `1.2.3` -> `2.0.0`
+    Major,
+    /// `1.2.3` -> `1.3.0`
+    Minor,
+    /// `1.2.3` -> `1.2.4`
+    Patch,
+    /// `1.2.3` -> `2.0.0-beta.0`
+    Premajor(Option<String>),
+    /// `1.2.3` -> `1.3.0-beta.0`
+    Preminor(Option<String>),
+    /// `1.2.3` -> `1.2.4-beta.0`
+    Prepatch(Option<String>),
+    /// `1.0.0-beta.1` -> `1.0.0-beta.2`, or `1.2.3` -> `1.2.4-beta.0`
+    Prerelease(Option<String>),
+    /// `1.0.0-beta.2` -> `1.0.0`
+    Release,
+}
+
+/// Bumped contents of a Cargo.toml and package.json pair.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BumpedContents {
+    /// Version both files carried before the bump
+    pub previous_version: semver::Version,
+    /// Version both files carry after the bump
+    pub new_version: semver::Version,
+    /// Patched Cargo.toml contents
+    pub cargo_toml: String,
+    /// Patched package.json contents
+    pub package_json: String,
+}
+
+/// Computes the next version, following npm's `semver.inc` rules.
+///
+/// Bumping a pre-release to the release it leads up to drops the pre-release
+/// instead of skipping ahead, so `2.0.0-beta.1` bumped by `Major` is `2.0.0` and
+/// `1.2.3-beta.1` bumped by `Patch` is `1.2.3`. `Prerelease` increments the last
+/// numeric identifier, and starts over at `<id>.0` when given a different
+/// identifier. Build metadata is always dropped.
+///
+/// # Errors
+///
+/// * `Error::NotAPrerelease` - `Release` was asked of a version that is not a
+///   pre-release
+/// * `Error::Semver` - The pre-release identifier is not valid semver
+///
+/// # Examples
+///
+/// ```rust
+/// use package_version_sync::{bump_version, BumpKind};
+/// use semver::Version;
+///
+/// let version = Version::parse("1.2.3")?;
+/// assert_eq!(bump_version(&version, BumpKind::Minor)?, Version::parse("1.3.0")?);
+///
+/// let version = Version::parse("1.0.0-beta.1")?;
+/// assert_eq!(
+///     bump_version(&version, BumpKind::Prerelease(None))?,
+///     Version::parse("1.0.0-beta.2")?
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn bump_version(version: &semver::Version, kind: BumpKind) -> Result<semver::Version, Error> {
+    let is_prerelease = !version.pre.is_empty();
+    let (major, minor, patch) = (version.major, version.minor, version.patch);
+    let (major, minor, patch, pre) = match kind {
+        BumpKind::Major if is_prerelease && minor == 0 && patch == 0 => (major, 0, 0, None),
+        BumpKind::Major => (major + 1, 0, 0, None),
+        BumpKind::Minor if is_prerelease && patch == 0 => (major, minor, 0, None),
+        BumpKind::Minor => (major, minor + 1, 0, None),
+        BumpKind::Patch if is_prerelease => (major, minor, patch, None),
+        BumpKind::Patch => (major, minor, patch + 1, None),
+        BumpKind::Premajor(id) => (major + 1, 0, 0, Some(first_prerelease(id))),
+        BumpKind::Preminor(id) => (major, minor + 1, 0, Some(first_prerelease(id))),
+        BumpKind::Prepatch(id) => (major, minor, patch + 1, Some(first_prerelease(id))),
+        BumpKind::Prerelease(id) if is_prerelease => (
+            major,
+            minor,
+            patch,
+            Some(next_prerelease(version.pre.as_str(), id)),
+        ),
+        BumpKind::Prerelease(id) => (major, minor, patch + 1, Some(first_prerelease(id))),
+        BumpKind::Release if is_prerelease => (major, minor, patch, None),
+        BumpKind::Release => {
+            return Err(Error::NotAPrerelease {
+                version: version.clone(),
+            });
+        }
+    };
+
+    let mut bumped = semver::Version::new(major, minor, patch);
+    if let Some(pre) = pre {
+        bumped.pre = semver::Prerelease::new(&pre).map_err(Error::Semver)?;
+    }
+    Ok(bumped)
+}
+
+fn first_prerelease(id: Option<String>) -> String {
+    match id {
+        Some(id) => format!("{}.0", id),
+        None => "0".to_string(),
+    }
+}
+
+fn next_prerelease(current: &str, id: Option<String>) -> String {
+    let mut identifiers = current.split('.').map(str::to_string).collect::<Vec<_>>();
+    match identifiers
+        .iter_mut()
+        .rev()
+        .find_map(|identifier| identifier.parse::<u64>().ok().map(|n| (identifier, n)))
+    {
+        Some((identifier, n)) => *identifier = (n + 1).to_string(),
+        None => identifiers.push("0".to_string()),
+    }
+
+    match id {
+        // A new identifier starts its own count, as does one with no number after it.
+        Some(id)
+            if identifiers[0] != id
+                || identifiers.get(1).is_none_or(|n| n.parse::<u64>().is_err()) =>
+        {
+            first_prerelease(Some(id))
+        }
+        _ => identifiers.join("."),
+    }
+}
+
+/// Advances the shared version of a set of manifests.
+///
+/// Every manifest must already carry the same version; the next version is computed
+/// with `bump_version` and written to all of them in memory.
+///
+/// # Returns
+///
+/// One `ManifestChange` per manifest, in slice order. Call `Manifest::save` on each
+/// to write the changes to disk.
+///
+/// # Errors
+///
+/// * `Error::MissingCargoVersion`, `Error::MissingPackageJsonVersion`,
+///   `Error::NonStringVersion` - A manifest has no usable version
+/// * `Error::OutOfSync` - The manifests disagree
+/// * `Error::NotAPrerelease` - `Release` was asked of a version that is not a
+///   pre-release
+/// * `Error::Semver` - Invalid semantic version strings or pre-release identifier
+/// * `Error::PatchFailed` - A rewritten manifest did not carry the new version
+pub fn bump_manifests(
+    manifests: &mut [Box<dyn Manifest>],
+    kind: BumpKind,
+) -> Result<Vec<ManifestChange>, Error> {
+    let versions = manifests
+        .iter()
+        .map(|manifest| manifest.version())
+        .collect::<Result<Vec<_>, Error>>()?;
+    let Some(current_version) = versions.first() else {
+        return Ok(Vec::new());
+    };
+    let current_version_semver = parse_version(manifests[0].as_ref(), current_version)?;
+    for (manifest, version) in manifests.iter().zip(&versions).skip(1) {
+        let version_semver = parse_version(manifest.as_ref(), version)?;
+        if version_semver.ne(&current_version_semver) {
+            return Err(Error::OutOfSync {
+                first: current_version.clone(),
+                other: version.clone(),
+            });
+        }
+    }
+
+    let new_version = bump_version(&current_version_semver, kind)?.to_string();
+    let mut changes = Vec::new();
+    for (index, (manifest, version)) in manifests.iter_mut().zip(versions).enumerate() {
+        manifest.set_version(&new_version)?;
+        changes.push(ManifestChange {
+            index,
+            kind: manifest.kind(),
+            path: manifest.path().to_path_buf(),
+            previous_version: version,
+            new_version: new_version.clone(),
+        });
+    }
+
+    Ok(changes)
+}
+
+/// Advances the version shared by Cargo.toml and package.json contents.
+///
+/// Both files must already carry the same version, as after `sync_contents`; the
+/// next version is computed with `bump_version` and written to both.
+///
+/// # Returns
+///
+/// The previous and new versions, with the patched contents of both files.
+///
+/// # Errors
+///
+/// Returns the same errors as `bump_manifests`.
+///
+/// # Examples
+///
+/// ```rust
+/// use package_version_sync::{bump_contents, BumpKind};
+///
+/// let cargo_toml = r#"[package]
+/// name = "my-app"
+/// version = "1.0.0-beta.1"
+/// "#;
+///
+/// let package_json = r#"{
+///   "name": "my-app",
+///   "version": "1.0.0-beta.1"
+/// }"#;
+///
+/// let bumped = bump_contents(cargo_toml, package_json, BumpKind::Prerelease(None))?;
+/// assert_eq!(bumped.new_version.to_string(), "1.0.0-beta.2");
+/// assert!(bumped.package_json.contains(r#""version": "1.0.0-beta.2""#));
+/// # Ok::<(), package_version_sync::Error>(())
+/// ```
+pub fn bump_contents(
+    cargo_toml_contents: &str,
+    package_json_contents: &str,
+    kind: BumpKind,
+) -> Result<BumpedContents, Error> {
+    let mut manifests: Vec<Box<dyn Manifest>> = vec![
+        Box::new(CargoToml::from_contents(
+            "Cargo.toml",
+            cargo_toml_contents,
+            None,
+        )?),
+        Box::new(PackageJson::from_contents(
+            "package.json",
+            package_json_contents,
+        )),
+    ];
+
+    let changes = bump_manifests(&mut manifests, kind)?;
+    let change = &changes[0];
+    Ok(BumpedContents {
+        previous_version: semver::Version::parse(&change.previous_version)
+            .map_err(Error::Semver)?,
+        new_version: semver::Version::parse(&change.new_version).map_err(Error::Semver)?,
+        cargo_toml: manifests[0].contents().to_string(),
+        package_json: manifests[1].contents().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bump(version: &str, kind: BumpKind) -> String {
+        bump_version(&semver::Version::parse(version).unwrap(), kind)
+            .unwrap()
+            .to_string()
+    }
+
+    fn beta() -> Option<String> {
+        Some("beta".to_string())
+    }
+
+    #[test]
+    fn test_bump_release_versions() {
+        assert_eq!(bump("1.2.3", BumpKind::Major), "2.0.0");
+        assert_eq!(bump("1.2.3", BumpKind::Minor), "1.3.0");
+        assert_eq!(bump("1.2.3", BumpKind::Patch), "1.2.4");
+        assert_eq!(bump("1.2.3+build.1", BumpKind::Patch), "1.2.4");
+    }
+
+    #[test]
+    fn test_bump_pre_release_versions() {
+        assert_eq!(bump("2.0.0-beta.1", BumpKind::Major), "2.0.0");
+        assert_eq!(bump("2.1.0-beta.1", BumpKind::Major), "3.0.0");
+        assert_eq!(bump("1.3.0-beta.1", BumpKind::Minor), "1.3.0");
+        assert_eq!(bump("1.3.1-beta.1", BumpKind::Minor), "1.4.0");
+        assert_eq!(bump("1.2.3-beta.1", BumpKind::Patch), "1.2.3");
+    }
+
+    #[test]
+    fn test_bump_to_pre_release() {
+        assert_eq!(bump("1.2.3", BumpKind::Premajor(None)), "2.0.0-0");
+        assert_eq!(bump("1.2.3", BumpKind::Premajor(beta())), "2.0.0-beta.0");
+        assert_eq!(bump("1.2.3", BumpKind::Preminor(beta())), "1.3.0-beta.0");
+        assert_eq!(bump("1.2.3", BumpKind::Prepatch(beta())), "1.2.4-beta.0");
+        assert_eq!(bump("1.2.3", BumpKind::Prerelease(beta())), "1.2.4-beta.0");
+        assert_eq!(bump("1.0.0-beta.1", BumpKind::Premajor(None)), "2.0.0-0");
+    }
+
+    #[test]
+    fn test_bump_prerelease_increments() {
+        assert_eq!(
+            bump("1.0.0-beta.1", BumpKind::Prerelease(None)),
+            "1.0.0-beta.2"
+        );
+        assert_eq!(
+            bump("1.0.0-beta.1", BumpKind::Prerelease(beta())),
+            "1.0.0-beta.2"
+        );
+        assert_eq!(
+            bump("1.0.0-beta.1", BumpKind::Prerelease(Some("rc".to_string()))),
+            "1.0.0-rc.0"
+        );
+        assert_eq!(
+            bump("1.0.0-beta", BumpKind::Prerelease(None)),
+            "1.0.0-beta.0"
+        );
+        assert_eq!(
+            bump("1.0.0-beta", BumpKind::Prerelease(beta())),
+            "1.0.0-beta.0"
+        );
+        assert_eq!(bump("1.0.0-3", BumpKind::Prerelease(None)), "1.0.0-4");
+        assert_eq!(
+            bump("1.0.0-beta.1.x", BumpKind::Prerelease(None)),
+            "1.0.0-beta.2.x"
+        );
+    }
+
+    #[test]
+    fn test_bump_release() {
+        assert_eq!(bump("1.0.0-beta.2", BumpKind::Release), "1.0.0");
+
+        let result = bump_version(&semver::Version::new(1, 0, 0), BumpKind::Release);
+
+        assert!(matches!(
+            result.unwrap_err(),
+            Error::NotAPrerelease { version } if version == semver::Version::new(1, 0, 0)
+        ));
+    }
+
+    #[test]
+    fn test_bump_contents() {
+        let bumped = bump_contents(
+            "[package]\nname = \"test-package\"\nversion = \"1.0.0-beta.1\"\n",
+            "{\n  \"name\": \"test-package\",\n  \"version\": \"1.0.0-beta.1\"\n}\n",
+            BumpKind::Prerelease(None),
+        )
+        .unwrap();
+
+        assert_eq!(bumped.previous_version.to_string(), "1.0.0-beta.1");
+        assert_eq!(bumped.new_version.to_string(), "1.0.0-beta.2");
+        assert_eq!(
+            bumped.cargo_toml,
+            "[package]\nname = \"test-package\"\nversion = \"1.0.0-beta.2\"\n"
+        );
+        assert_eq!(
+            bumped.package_json,
+            "{\n  \"name\": \"test-package\",\n  \"version\": \"1.0.0-beta.2\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_bump_manifests() {
+        let mut manifests: Vec<Box<dyn Manifest>> = vec![
+            Box::new(
+                CargoToml::from_contents(
+                    "Cargo.toml",
+                    "[package]\nname = \"test-package\"\nversion = \"1.2.3\"\n",
+                    None,
+                )
+                .unwrap(),
+            ),
+            Box::new(PackageJson::from_contents(
+                "package.json",
+                r#"{ "name": "test-package", "version": "1.2.3" }"#,
+            )),
+        ];
+
+        let changes = bump_manifests(&mut manifests, BumpKind::Minor).unwrap();
+
+        assert_eq!(changes.len(), 2);
+        for manifest in &manifests {
+            assert_eq!(manifest.version().unwrap(), "1.3.0");
+        }
+    }
+
+    #[test]
+    fn test_bump_manifests_out_of_sync() {
+        let mut manifests: Vec<Box<dyn Manifest>> = vec![
+            Box::new(PackageJson::from_contents(
+                "package.json",
+                r#"{ "version": "1.2.3" }"#,
+            )),
+            Box::new(PackageJson::from_contents(
+                "npm/package.json",
+                r#"{ "version": "1.2.4" }"#,
+            )),
+        ];
+
+        let result = bump_manifests(&mut manifests, BumpKind::Patch);
+
+        assert!(matches!(
+            result.unwrap_err(),
+            Error::OutOfSync { first, other } if first == "1.2.3" && other == "1.2.4"
+        ));
+    }
+}
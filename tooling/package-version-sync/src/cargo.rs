@@ -0,0 +1,580 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use crate::Error;
+use crate::discovery::cargo_members;
+use crate::manifest::{Manifest, ManifestKind, verify_patch};
+
+/// Where the effective version of a Cargo.toml is declared.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CargoVersionSource {
+    /// `[package].version` in the crate's own manifest
+    Package,
+    /// `[workspace.package].version` in the crate's own manifest, which is also the
+    /// workspace root
+    OwnWorkspace,
+    /// `[workspace.package].version` in a separate workspace root manifest
+    Workspace,
+}
+
+/// A Cargo.toml whose version can be read and rewritten in place.
+///
+/// A crate declaring `version.workspace = true` is backed by the manifest that
+/// actually holds `[workspace.package].version`, so `path` and `contents` refer to
+/// the workspace root whenever the version is inherited from a separate manifest.
+#[derive(Clone, Debug)]
+pub struct CargoToml {
+    path: PathBuf,
+    contents: String,
+    version_source: CargoVersionSource,
+    package_name: Option<String>,
+}
+
+impl CargoToml {
+    /// Wraps Cargo.toml contents that are already in memory.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - Path the contents were read from
+    /// * `contents` - Raw Cargo.toml file contents
+    /// * `workspace` - Path and raw contents of the workspace root Cargo.toml, used
+    ///   when the crate inherits its version from it
+    ///
+    /// # Errors
+    ///
+    /// Both tied to `path` through `Error::InFile`:
+    ///
+    /// * `Error::Toml` - Invalid TOML in Cargo.toml
+    /// * `Error::InheritedVersionUnresolved` - The version is inherited but `workspace`
+    ///   is `None`
+    pub fn from_contents(
+        path: impl Into<PathBuf>,
+        contents: impl Into<String>,
+        workspace: Option<(PathBuf, String)>,
+    ) -> Result<Self, Error> {
+        let path = path.into();
+        let contents = contents.into();
+        let package_name = read_package_name(&contents);
+        if !cargo_toml_inherits_version(&contents)
+            .map_err(|error| error.in_file(&path, Some(&contents)))?
+        {
+            return Ok(Self {
+                path,
+                contents,
+                version_source: CargoVersionSource::Package,
+                package_name,
+            });
+        }
+        if read_workspace_version(&contents).is_ok() {
+            return Ok(Self {
+                path,
+                contents,
+                version_source: CargoVersionSource::OwnWorkspace,
+                package_name,
+            });
+        }
+
+        let (workspace_path, workspace_contents) = workspace
+            .ok_or_else(|| Error::InheritedVersionUnresolved.in_file(&path, Some(&contents)))?;
+        Ok(Self {
+            path: workspace_path,
+            contents: workspace_contents,
+            version_source: CargoVersionSource::Workspace,
+            package_name,
+        })
+    }
+
+    /// Reads a Cargo.toml from disk, following `version.workspace = true` to the
+    /// workspace root found by `find_workspace_root`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Io` when a manifest cannot be read, plus the errors of
+    /// `CargoToml::from_contents`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let contents = read_file(path)?;
+
+        let mut workspace = None;
+        if cargo_toml_inherits_version(&contents)
+            .map_err(|error| error.in_file(path, Some(&contents)))?
+            && read_workspace_version(&contents).is_err()
+            && let Some(workspace_path) = find_workspace_root(path)?
+        {
+            let workspace_contents = read_file(&workspace_path)?;
+            workspace = Some((workspace_path, workspace_contents));
+        }
+
+        Self::from_contents(path, contents, workspace)
+    }
+
+    /// Whether the version lives in a separate workspace root manifest, in which case
+    /// `path` and `contents` refer to that manifest rather than the crate's own.
+    pub fn inherits_from_workspace_root(&self) -> bool {
+        self.version_source == CargoVersionSource::Workspace
+    }
+
+    /// Whether the manifest at `path` declares `[workspace]`, making it the root of
+    /// its own workspace. Always true when the version lives in a separate workspace
+    /// root.
+    pub(crate) fn declares_workspace(&self) -> bool {
+        toml::from_str::<toml::Table>(&self.contents)
+            .is_ok_and(|manifest| manifest.contains_key("workspace"))
+    }
+
+    /// Whether the version is inherited from `[workspace.package].version`, either
+    /// in a separate workspace root or in the crate's own manifest.
+    pub(crate) fn inherits_version(&self) -> bool {
+        self.version_source != CargoVersionSource::Package
+    }
+
+    /// The crate's `[package].name`, read from its own manifest even when the version
+    /// lives in the workspace root.
+    pub fn package_name(&self) -> Option<&str> {
+        self.package_name.as_deref()
+    }
+
+    fn locate(&self, error: Error) -> Error {
+        match error {
+            Error::NonStringVersion { .. } => {
+                let span = self.version_span();
+                error.at(&self.path, Some(&self.contents), span)
+            }
+            error => error.in_file(&self.path, Some(&self.contents)),
+        }
+    }
+}
+
+impl Manifest for CargoToml {
+    fn kind(&self) -> ManifestKind {
+        if self.inherits_from_workspace_root() {
+            ManifestKind::WorkspaceCargoToml
+        } else {
+            ManifestKind::CargoToml
+        }
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    fn version(&self) -> Result<String, Error> {
+        match self.version_source {
+            CargoVersionSource::Package => read_cargo_toml_version(&self.contents),
+            _ => read_workspace_version(&self.contents),
+        }
+        .map_err(|error| self.locate(error))
+    }
+
+    fn version_span(&self) -> Option<Range<usize>> {
+        let key_path: &[&str] = match self.version_source {
+            CargoVersionSource::Package => &["package", "version"],
+            _ => &["workspace", "package", "version"],
+        };
+        let document = toml_edit::Document::parse(self.contents.as_str()).ok()?;
+        key_path
+            .iter()
+            .try_fold(document.as_item(), |item, key| item.get(key))?
+            .span()
+    }
+
+    fn set_version(&mut self, version: &str) -> Result<(), Error> {
+        self.contents = match self.version_source {
+            CargoVersionSource::Package => update_cargo_toml(&self.contents, version),
+            _ => update_workspace_version(&self.contents, version),
+        }
+        .map_err(|error| self.locate(error))?;
+        Ok(())
+    }
+
+    fn has_version(&self) -> Result<bool, Error> {
+        match self.version_source {
+            CargoVersionSource::Package => {
+                let parsed_cargo_toml = toml::from_str::<toml::Table>(&self.contents)
+                    .map_err(|error| self.locate(Error::Toml(error)))?;
+                Ok(parsed_cargo_toml
+                    .get("package")
+                    .is_some_and(|package| package.get("version").is_some()))
+            }
+            _ => Ok(true),
+        }
+    }
+
+    fn seed_version(&mut self, version: &str) -> Result<(), Error> {
+        self.contents = insert_cargo_toml_version(&self.contents, version)
+            .and_then(|patched| {
+                verify_patch("Cargo.toml", patched, version, read_cargo_toml_version)
+            })
+            .map_err(|error| self.locate(error))?;
+        Ok(())
+    }
+}
+
+/// Finds the workspace root manifest for the crate at `cargo_toml_path`.
+///
+/// Walks up from the crate's directory, the same way Cargo does, and returns the
+/// first ancestor Cargo.toml that declares a `[workspace]` table. The crate's own
+/// manifest is not considered. The root is returned relative to the same directory
+/// as `cargo_toml_path` when that is relative, such as `../../Cargo.toml`.
+///
+/// # Errors
+///
+/// Returns `Error::Io` when the path cannot be made absolute or an ancestor manifest
+/// cannot be read, and `Error::Toml` when an ancestor manifest is invalid.
+pub fn find_workspace_root(cargo_toml_path: &Path) -> Result<Option<PathBuf>, Error> {
+    let Some(crate_dir) = cargo_toml_path.parent() else {
+        return Ok(None);
+    };
+    // The absolute path only tells how far up there is to go; candidates are joined
+    // onto the caller's own path so relative paths stay relative.
+    let absolute_dir = normalize(&std::path::absolute(cargo_toml_path).map_err(Error::Io)?);
+    let levels = absolute_dir.ancestors().count().saturating_sub(2);
+
+    let mut dir = crate_dir.to_path_buf();
+    for _ in 0..levels {
+        dir = parent_dir(&dir);
+        let candidate = dir.join("Cargo.toml");
+        if !candidate.is_file() {
+            continue;
+        }
+        let contents = read_file(&candidate)?;
+        let parsed = toml::from_str::<toml::Table>(&contents)
+            .map_err(|error| Error::Toml(error).in_file(&candidate, Some(&contents)))?;
+        if parsed.contains_key("workspace") {
+            return Ok(Some(candidate));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Resolves `.` and `..` components lexically, as Cargo does, so counting the
+/// ancestors of an absolute path counts real directories.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The directory above `dir`, spelled the way `dir` is: `crates` for `crates/a`,
+/// `..` for `` or `.`, and `../..` for `..`.
+fn parent_dir(dir: &Path) -> PathBuf {
+    match dir.components().next_back() {
+        Some(Component::Normal(_)) => dir.parent().unwrap_or(Path::new("")).to_path_buf(),
+        Some(Component::CurDir) | None => PathBuf::from(".."),
+        Some(_) => dir.join(".."),
+    }
+}
+
+/// The `[package].name` of every crate of the workspace whose root manifest is
+/// `workspace_root` that declares `version.workspace = true`, the root's own package
+/// included.
+///
+/// # Errors
+///
+/// Returns `Error::Io` when a manifest cannot be read, `Error::Toml` when one is
+/// invalid, and `Error::Glob` for an invalid `members` pattern.
+pub(crate) fn inheriting_packages(workspace_root: &Path) -> Result<Vec<String>, Error> {
+    let root_dir = match workspace_root.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut paths = vec![workspace_root.to_path_buf()];
+    paths.extend(
+        cargo_members(root_dir)?
+            .into_iter()
+            .map(|dir| root_dir.join(dir).join("Cargo.toml")),
+    );
+
+    let mut names = Vec::new();
+    for path in paths.iter().filter(|path| path.is_file()) {
+        let contents = read_file(path)?;
+        if cargo_toml_inherits_version(&contents)
+            .map_err(|error| error.in_file(path, Some(&contents)))?
+            && let Some(name) = read_package_name(&contents)
+            && !names.contains(&name)
+        {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+fn read_file(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|error| Error::Io(error).in_file(path, None))
+}
+
+fn cargo_toml_inherits_version(cargo_toml_contents: &str) -> Result<bool, Error> {
+    let parsed_cargo_toml =
+        toml::from_str::<toml::Table>(cargo_toml_contents).map_err(Error::Toml)?;
+    Ok(parsed_cargo_toml
+        .get("package")
+        .and_then(|package| package.get("version"))
+        .and_then(|version| version.get("workspace"))
+        .and_then(|workspace| workspace.as_bool())
+        .unwrap_or(false))
+}
+
+fn read_package_name(cargo_toml_contents: &str) -> Option<String> {
+    toml::from_str::<toml::Table>(cargo_toml_contents)
+        .ok()?
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn read_cargo_toml_version(cargo_toml_contents: &str) -> Result<String, Error> {
+    let parsed_cargo_toml =
+        toml::from_str::<toml::Table>(cargo_toml_contents).map_err(Error::Toml)?;
+    let version = parsed_cargo_toml
+        .get("package")
+        .and_then(|package| package.get("version"))
+        .ok_or(Error::MissingCargoVersion)?;
+    toml_version_string(version)
+}
+
+fn read_workspace_version(cargo_toml_contents: &str) -> Result<String, Error> {
+    let parsed_cargo_toml =
+        toml::from_str::<toml::Table>(cargo_toml_contents).map_err(Error::Toml)?;
+    let version = parsed_cargo_toml
+        .get("workspace")
+        .and_then(|workspace| workspace.get("package"))
+        .and_then(|package| package.get("version"))
+        .ok_or(Error::InheritedVersionUnresolved)?;
+    toml_version_string(version)
+}
+
+fn toml_version_string(version: &toml::Value) -> Result<String, Error> {
+    version
+        .as_str()
+        .map(str::to_string)
+        .ok_or(Error::NonStringVersion {
+            found_type: version.type_str(),
+        })
+}
+
+fn update_cargo_toml(cargo_toml_contents: &str, new_version: &str) -> Result<String, Error> {
+    verify_patch(
+        "Cargo.toml",
+        patch_cargo_toml_version(cargo_toml_contents, new_version)?,
+        new_version,
+        read_cargo_toml_version,
+    )
+}
+
+fn update_workspace_version(cargo_toml_contents: &str, new_version: &str) -> Result<String, Error> {
+    verify_patch(
+        "workspace Cargo.toml",
+        patch_workspace_version(cargo_toml_contents, new_version)?,
+        new_version,
+        read_workspace_version,
+    )
+}
+
+/// Rewrites `package.version` in place, leaving every other item, comment, and
+/// whitespace run untouched.
+fn patch_cargo_toml_version(cargo_toml_contents: &str, new_version: &str) -> Result<String, Error> {
+    patch_toml_string(
+        cargo_toml_contents,
+        &["package", "version"],
+        new_version,
+        Error::MissingCargoVersion,
+    )
+}
+
+/// Rewrites `workspace.package.version` in place, the version that members declaring
+/// `version.workspace = true` inherit.
+fn patch_workspace_version(cargo_toml_contents: &str, new_version: &str) -> Result<String, Error> {
+    patch_toml_string(
+        cargo_toml_contents,
+        &["workspace", "package", "version"],
+        new_version,
+        Error::InheritedVersionUnresolved,
+    )
+}
+
+/// Inserts `package.version` right after `package.name`, or at the end of
+/// `[package]` when there is no `name`.
+fn insert_cargo_toml_version(
+    cargo_toml_contents: &str,
+    new_version: &str,
+) -> Result<String, Error> {
+    let mut document = cargo_toml_contents
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(Error::TomlEdit)?;
+    let package = document
+        .get_mut("package")
+        .and_then(|package| package.as_table_mut())
+        .ok_or(Error::MissingCargoPackage)?;
+
+    // Tables only append, so the keys after `name` are moved behind `version`.
+    let keys = package
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .skip_while(|key| key != "name")
+        .skip(1)
+        .collect::<Vec<_>>();
+    let moved = keys
+        .iter()
+        .filter_map(|key| package.remove_entry(key))
+        .collect::<Vec<_>>();
+    package.insert("version", toml_edit::value(new_version));
+    for (key, item) in moved {
+        package.insert_formatted(&key, item);
+    }
+
+    Ok(document.to_string())
+}
+
+fn patch_toml_string(
+    toml_contents: &str,
+    key_path: &[&str],
+    new_value: &str,
+    not_found: Error,
+) -> Result<String, Error> {
+    let mut document = toml_contents
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(Error::TomlEdit)?;
+    let value = key_path
+        .iter()
+        .try_fold(document.as_item_mut(), |item, key| item.get_mut(key))
+        .and_then(|item| item.as_value_mut())
+        .filter(|value| value.is_str())
+        .ok_or(not_found)?;
+    replace_toml_string(value, new_value);
+
+    Ok(document.to_string())
+}
+
+/// Replaces `value` with the string `new_value`, keeping the comments and
+/// whitespace around it.
+pub(crate) fn replace_toml_string(value: &mut toml_edit::Value, new_value: &str) {
+    let decor = value.decor().clone();
+    *value = toml_edit::Value::from(new_value);
+    *value.decor_mut() = decor;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write;
+
+    const WORKSPACE_CARGO_TOML: &str = r#"[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "1.0.0"
+"#;
+
+    #[test]
+    fn test_set_version_carries_new_version() {
+        let mut cargo_toml = CargoToml::from_contents(
+            "Cargo.toml",
+            "[package]\nname = \"test-package\"\nversion = \"1.0.0\"\n",
+            None,
+        )
+        .unwrap();
+
+        cargo_toml.set_version("1.2.0").unwrap();
+
+        assert_eq!(cargo_toml.version().unwrap(), "1.2.0");
+        assert_eq!(
+            read_cargo_toml_version(cargo_toml.contents()).unwrap(),
+            "1.2.0"
+        );
+    }
+
+    #[test]
+    fn test_inherited_version_is_backed_by_workspace_root() {
+        let mut cargo_toml = CargoToml::from_contents(
+            "crates/test-package/Cargo.toml",
+            "[package]\nname = \"test-package\"\nversion.workspace = true\n",
+            Some((
+                PathBuf::from("Cargo.toml"),
+                WORKSPACE_CARGO_TOML.to_string(),
+            )),
+        )
+        .unwrap();
+
+        cargo_toml.set_version("1.1.0").unwrap();
+
+        assert!(cargo_toml.inherits_from_workspace_root());
+        assert_eq!(cargo_toml.kind(), ManifestKind::WorkspaceCargoToml);
+        assert_eq!(cargo_toml.path(), Path::new("Cargo.toml"));
+        assert_eq!(
+            read_workspace_version(cargo_toml.contents()).unwrap(),
+            "1.1.0"
+        );
+    }
+
+    #[test]
+    fn test_find_workspace_root_keeps_relative_paths_relative() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "Cargo.toml", WORKSPACE_CARGO_TOML);
+        write(
+            root.path(),
+            "crates/a/Cargo.toml",
+            "[package]\nname = \"a\"\n",
+        );
+        // The temporary directory, reached from the current one through `..`.
+        let current_dir = std::env::current_dir().unwrap();
+        let relative_root = Path::new(&"../".repeat(current_dir.components().count() - 1))
+            .join(root.path().strip_prefix("/").unwrap());
+
+        let found = find_workspace_root(&relative_root.join("crates/a/Cargo.toml")).unwrap();
+
+        assert_eq!(found, Some(relative_root.join("Cargo.toml")));
+        assert_eq!(
+            find_workspace_root(&root.path().join("crates/a/Cargo.toml")).unwrap(),
+            Some(root.path().join("Cargo.toml"))
+        );
+    }
+
+    #[test]
+    fn test_parent_dir_keeps_the_spelling() {
+        let cases = [
+            ("crates/a", "crates"),
+            ("crates", ""),
+            ("", ".."),
+            (".", ".."),
+            ("..", "../.."),
+        ];
+
+        for (dir, expected) in cases {
+            assert_eq!(parent_dir(Path::new(dir)), Path::new(expected), "{}", dir);
+        }
+    }
+
+    #[test]
+    fn test_seed_version_after_name() {
+        let mut cargo_toml = CargoToml::from_contents(
+            "Cargo.toml",
+            "[package]\nname = \"test-package\" # the name\nedition = \"2024\"\n\n[dependencies]\nserde = \"1\"\n",
+            None,
+        )
+        .unwrap();
+
+        assert!(!cargo_toml.has_version().unwrap());
+        cargo_toml.seed_version("1.2.0").unwrap();
+
+        assert!(cargo_toml.has_version().unwrap());
+        assert_eq!(
+            cargo_toml.contents(),
+            "[package]\nname = \"test-package\" # the name\nversion = \"1.2.0\"\nedition = \"2024\"\n\n[dependencies]\nserde = \"1\"\n"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use crate::lockfile::{find_lockfiles, sync_lockfiles};
+use crate::manifest::{Manifest, ManifestKind, SyncOptions, sync_manifests};
+use crate::{CargoToml, Error, PackageJson};
+
+/// How far apart the Cargo.toml and package.json versions are, as found by a
+/// read-only check.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drift {
+    /// Version found in Cargo.toml, or in the workspace root it inherits from.
+    /// `None` when it has none and `seed_missing_versions` is on.
+    pub cargo_version: Option<String>,
+    /// Version found in package.json. `None` when it has none and
+    /// `seed_missing_versions` is on.
+    pub package_json_version: Option<String>,
+    /// The files a sync would rewrite, in the order `sync_files` writes them: the
+    /// manifests, then the lockfiles. Empty when the versions already agree
+    pub would_update: Vec<ManifestKind>,
+}
+
+impl Drift {
+    /// Whether a sync would leave both files untouched.
+    pub fn is_in_sync(&self) -> bool {
+        self.would_update.is_empty()
+    }
+}
+
+/// Checks whether Cargo.toml and package.json contents are in sync without changing
+/// either.
+///
+/// Makes the same decision `sync_contents` would, and reports it instead of
+/// returning patched contents.
+///
+/// # Errors
+///
+/// Returns the same errors as `sync_contents`.
+///
+/// # Examples
+///
+/// ```rust
+/// use package_version_sync::{check_contents, ManifestKind};
+///
+/// let cargo_toml = r#"[package]
+/// name = "my-app"
+/// version = "1.0.0"
+/// "#;
+///
+/// let package_json = r#"{
+///   "name": "my-app",
+///   "version": "1.1.0"
+/// }"#;
+///
+/// let drift = check_contents(cargo_toml, package_json, None)?;
+/// assert_eq!(drift.would_update, [ManifestKind::CargoToml]);
+/// # Ok::<(), package_version_sync::Error>(())
+/// ```
+pub fn check_contents(
+    cargo_toml_contents: &str,
+    package_json_contents: &str,
+    options: impl Into<SyncOptions>,
+) -> Result<Drift, Error> {
+    check_manifests(
+        CargoToml::from_contents("Cargo.toml", cargo_toml_contents, None)?,
+        PackageJson::from_contents("package.json", package_json_contents),
+        Vec::new(),
+        options,
+    )
+}
+
+/// Checks whether Cargo.toml and package.json files on disk are in sync.
+///
+/// The read-only counterpart of `sync_files`: files are read, including the
+/// workspace root for inherited versions and the lockfiles `sync_files` would bring
+/// along, but nothing is ever written. A stale lockfile is drift like any other.
+///
+/// # Errors
+///
+/// Returns the same errors as `sync_files`.
+pub fn check_files(
+    cargo_toml_path: &str,
+    package_json_path: &str,
+    options: impl Into<SyncOptions>,
+) -> Result<Drift, Error> {
+    let options = options.into();
+    let cargo_toml = CargoToml::read(cargo_toml_path)?;
+    let package_json = PackageJson::read(package_json_path)?;
+    let lockfiles = find_lockfiles(&cargo_toml, &package_json, options.update_package_lock)?;
+    check_manifests(cargo_toml, package_json, lockfiles, options)
+}
+
+fn check_manifests(
+    cargo_toml: CargoToml,
+    package_json: PackageJson,
+    lockfiles: Vec<Box<dyn Manifest>>,
+    options: impl Into<SyncOptions>,
+) -> Result<Drift, Error> {
+    let options = options.into();
+    let read_version = |manifest: &dyn Manifest| -> Result<Option<String>, Error> {
+        if options.seed_missing_versions && !manifest.has_version()? {
+            return Ok(None);
+        }
+        manifest.version().map(Some)
+    };
+    let cargo_version = read_version(&cargo_toml)?;
+    let package_json_version = read_version(&package_json)?;
+
+    // The decision is made on throwaway copies, so the caller's files are never
+    // touched.
+    let mut manifests: Vec<Box<dyn Manifest>> = vec![Box::new(cargo_toml), Box::new(package_json)];
+    let mut report = sync_manifests(&mut manifests, options)?;
+    sync_lockfiles(lockfiles, &mut manifests, &mut report)?;
+
+    Ok(Drift {
+        cargo_version,
+        package_json_version,
+        would_update: report.updated_files().map(|(_, file)| file.kind).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_support::{create_cargo_toml, create_package_json, write};
+    use crate::{SourceOfTruth, SyncPolicy};
+
+    #[test]
+    fn test_check_in_sync() {
+        let drift = check_contents(
+            &create_cargo_toml("1.2.3"),
+            &create_package_json("1.2.3"),
+            None,
+        )
+        .unwrap();
+
+        assert!(drift.is_in_sync());
+        assert_eq!(drift.cargo_version.as_deref(), Some("1.2.3"));
+        assert_eq!(drift.package_json_version.as_deref(), Some("1.2.3"));
+    }
+
+    #[test]
+    fn test_check_reports_file_to_update() {
+        let drift = check_contents(
+            &create_cargo_toml("1.2.4"),
+            &create_package_json("1.2.3"),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            drift,
+            Drift {
+                cargo_version: Some("1.2.4".to_string()),
+                package_json_version: Some("1.2.3".to_string()),
+                would_update: vec![ManifestKind::PackageJson],
+            }
+        );
+    }
+
+    #[test]
+    fn test_check_respects_source_of_truth() {
+        let drift = check_contents(
+            &create_cargo_toml("1.2.4"),
+            &create_package_json("1.2.3"),
+            SyncOptions {
+                policy: SyncPolicy::SourceOfTruth(SourceOfTruth::PackageJson),
+                allow_downgrade: true,
+                ..SyncOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(drift.would_update, [ManifestKind::CargoToml]);
+    }
+
+    #[test]
+    fn test_check_reports_version_to_seed() {
+        let drift = check_contents(
+            &create_cargo_toml("1.2.3"),
+            "{\n  \"name\": \"test-package\"\n}\n",
+            SyncOptions {
+                seed_missing_versions: true,
+                ..SyncOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(drift.package_json_version, None);
+        assert_eq!(drift.would_update, [ManifestKind::PackageJson]);
+    }
+
+    #[test]
+    fn test_check_reports_both_files_to_update() {
+        let drift = check_contents(
+            &create_cargo_toml("1.2.3"),
+            &create_package_json("v1.2.4"),
+            SyncOptions {
+                lenient_versions: true,
+                canonicalize_versions: true,
+                ..SyncOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            drift.would_update,
+            [ManifestKind::CargoToml, ManifestKind::PackageJson]
+        );
+        assert!(!drift.is_in_sync());
+    }
+
+    #[test]
+    fn test_check_files_reports_stale_cargo_lock() {
+        let root = tempfile::tempdir().unwrap();
+        let cargo_lock = "[[package]]\nname = \"test-package\"\nversion = \"1.0.0\"\n";
+        write(root.path(), "Cargo.toml", &create_cargo_toml("1.1.0"));
+        write(root.path(), "package.json", &create_package_json("1.1.0"));
+        write(root.path(), "Cargo.lock", cargo_lock);
+
+        let drift = check_files(
+            &root.path().join("Cargo.toml").to_string_lossy(),
+            &root.path().join("package.json").to_string_lossy(),
+            None,
+        )
+        .unwrap();
+
+        assert!(!drift.is_in_sync());
+        assert_eq!(
+            fs::read_to_string(root.path().join("Cargo.lock")).unwrap(),
+            cargo_lock
+        );
+    }
+
+    #[test]
+    fn test_check_files_lists_stale_lockfiles_to_update() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "Cargo.toml", &create_cargo_toml("1.1.0"));
+        write(root.path(), "package.json", &create_package_json("1.0.0"));
+        write(
+            root.path(),
+            "Cargo.lock",
+            "[[package]]\nname = \"test-package\"\nversion = \"1.0.0\"\n",
+        );
+        write(
+            root.path(),
+            "package-lock.json",
+            "{ \"version\": \"1.0.0\" }\n",
+        );
+        let options = SyncOptions {
+            update_package_lock: true,
+            ..SyncOptions::default()
+        };
+
+        let drift = check_files(
+            &root.path().join("Cargo.toml").to_string_lossy(),
+            &root.path().join("package.json").to_string_lossy(),
+            options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            drift.would_update,
+            [
+                ManifestKind::PackageJson,
+                ManifestKind::CargoLock,
+                ManifestKind::PackageLock
+            ]
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::requirements::{RequirementChange, sync_requirements};
+use crate::{Error, SyncOptions, SyncReport, sync_files};
+
+/// A workspace directory holding both a Cargo.toml and a package.json.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WorkspacePackage {
+    /// Directory of the package, relative to the workspace root it was found from
+    pub dir: PathBuf,
+    /// Path to the package's Cargo.toml
+    pub cargo_toml: PathBuf,
+    /// Path to the package's package.json
+    pub package_json: PathBuf,
+}
+
+/// Outcome of syncing one package of a workspace.
+#[derive(Debug)]
+pub struct PackageSync {
+    /// The package that was synced
+    pub package: WorkspacePackage,
+    /// The package's report, or why it could not be synced
+    pub result: Result<SyncReport, Error>,
+}
+
+/// Outcome of syncing a whole workspace.
+#[derive(Debug)]
+pub struct WorkspaceSync {
+    /// One entry per package that carries both manifests, sorted by path
+    pub packages: Vec<PackageSync>,
+    /// Path dependency requirements rewritten to admit the versions the workspace
+    /// crates now carry
+    pub requirements: Vec<RequirementChange>,
+}
+
+#[derive(Deserialize)]
+struct PnpmWorkspace {
+    #[serde(default)]
+    packages: Vec<String>,
+    #[serde(default)]
+    overrides: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct CargoWorkspaceManifest {
+    workspace: Option<CargoWorkspace>,
+}
+
+#[derive(Deserialize)]
+struct CargoWorkspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+/// Finds the packages of the workspace rooted at `root` that carry both manifests.
+///
+/// Expands the `packages` globs of `root/pnpm-workspace.yaml`, where a leading `!`
+/// excludes matches, and the `[workspace].members` globs of `root/Cargo.toml`, less
+/// its `exclude` list. Either file may be missing. Every matched directory that
+/// contains both a Cargo.toml and a package.json is returned once, sorted by path.
+///
+/// # Errors
+///
+/// Errors about a workspace file are tied to it through `Error::InFile`.
+///
+/// * `Error::Io` - A workspace file or directory cannot be read
+/// * `Error::Yaml` - Invalid pnpm-workspace.yaml
+/// * `Error::Toml` - Invalid root Cargo.toml
+/// * `Error::Glob` - Invalid glob pattern
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use package_version_sync::discover_packages;
+///
+/// for package in discover_packages(".".as_ref())? {
+///     println!("{}", package.dir.display());
+/// }
+/// # Ok::<(), package_version_sync::Error>(())
+/// ```
+pub fn discover_packages(root: &Path) -> Result<Vec<WorkspacePackage>, Error> {
+    let mut dirs = BTreeSet::new();
+
+    dirs.extend(pnpm_packages(root)?);
+    dirs.extend(cargo_members(root)?);
+
+    Ok(dirs
+        .into_iter()
+        .map(|dir| WorkspacePackage {
+            cargo_toml: root.join(&dir).join("Cargo.toml"),
+            package_json: root.join(&dir).join("package.json"),
+            dir,
+        })
+        .filter(|package| package.cargo_toml.is_file() && package.package_json.is_file())
+        .collect())
+}
+
+/// The directories matched by the `packages` globs of `root/pnpm-workspace.yaml`,
+/// where a leading `!` excludes matches, relative to `root`. Empty when there is no
+/// such file.
+pub(crate) fn pnpm_packages(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let Some(workspace) = read_pnpm_workspace(root)? else {
+        return Ok(Vec::new());
+    };
+    let (excludes, includes): (Vec<_>, Vec<_>) = workspace
+        .packages
+        .iter()
+        .partition(|pattern| pattern.starts_with('!'));
+    let excludes = excludes
+        .iter()
+        .map(|pattern| &pattern[1..])
+        .collect::<Vec<_>>();
+    expand_globs(root, includes, excludes)
+}
+
+/// The directories of the pnpm workspace at `root` that hold a package.json, relative
+/// to `root`: the root itself first, then the `pnpm_packages`, sorted by path.
+pub(crate) fn pnpm_package_dirs(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut dirs = pnpm_packages(root)?;
+    dirs.retain(|dir| !dir.as_os_str().is_empty());
+    dirs.sort();
+    dirs.dedup();
+    dirs.insert(0, PathBuf::new());
+    dirs.retain(|dir| root.join(dir).join("package.json").is_file());
+    Ok(dirs)
+}
+
+/// The `overrides` of `root/pnpm-workspace.yaml`, keyed by the selector as written.
+/// Empty when there is no such file.
+pub(crate) fn pnpm_overrides(root: &Path) -> Result<BTreeMap<String, String>, Error> {
+    Ok(read_pnpm_workspace(root)?
+        .map(|workspace| workspace.overrides)
+        .unwrap_or_default())
+}
+
+fn read_pnpm_workspace(root: &Path) -> Result<Option<PnpmWorkspace>, Error> {
+    let pnpm_workspace_path = root.join("pnpm-workspace.yaml");
+    let Some(contents) = read_optional(&pnpm_workspace_path)? else {
+        return Ok(None);
+    };
+    serde_yaml::from_str(&contents)
+        .map(Some)
+        .map_err(|error| Error::Yaml(error).in_file(&pnpm_workspace_path, Some(&contents)))
+}
+
+/// The `[workspace].members` directories of `root/Cargo.toml`, less its `exclude`
+/// list, relative to `root`. Empty when there is no such manifest or workspace.
+pub(crate) fn cargo_members(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let cargo_toml_path = root.join("Cargo.toml");
+    let Some(contents) = read_optional(&cargo_toml_path)? else {
+        return Ok(Vec::new());
+    };
+    let manifest: CargoWorkspaceManifest = toml::from_str(&contents)
+        .map_err(|error| Error::Toml(error).in_file(&cargo_toml_path, Some(&contents)))?;
+    match manifest.workspace {
+        Some(workspace) => expand_globs(root, &workspace.members, &workspace.exclude),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Syncs every package `discover_packages` finds under `root`, then brings the
+/// path dependency requirements between the Cargo workspace members in step.
+///
+/// Packages are synced one after another with `sync_files`. A package that fails
+/// to sync does not stop the others; its error is kept in its `PackageSync`.
+///
+/// Once every package is synced, each `{ path = "...", version = "..." }`
+/// dependency in the member manifests and the root manifest, including
+/// `[workspace.dependencies]`, whose requirement no longer admits the version of
+/// the member it points at is rewritten. The operator is kept, so `"0.1"` becomes
+/// `"0.2"` and `"=0.1.0"` becomes `"=0.2.0"`; requirements with several comparators
+/// or other operators are left alone. Every member is checked, not only those
+/// synced in this run, so requirements left stale earlier are caught up too.
+///
+/// # Errors
+///
+/// Returns the errors of `discover_packages`, and any error rewriting the
+/// requirements. Packages synced before such an error stay written; running the
+/// sync again finishes the rewrite. Errors of individual packages are reported in
+/// the returned `packages` instead.
+pub fn sync_workspace(
+    root: &Path,
+    options: impl Into<SyncOptions>,
+) -> Result<WorkspaceSync, Error> {
+    let options = options.into();
+    let packages = discover_packages(root)?
+        .into_iter()
+        .map(|package| {
+            let result = sync_files(
+                &package.cargo_toml.to_string_lossy(),
+                &package.package_json.to_string_lossy(),
+                options.clone(),
+            );
+            PackageSync { package, result }
+        })
+        .collect();
+    let requirements = sync_requirements(root, &cargo_members(root)?)?;
+
+    Ok(WorkspaceSync {
+        packages,
+        requirements,
+    })
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>, Error> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(Error::Io(error).in_file(path, None)),
+    }
+}
+
+/// Expands `includes` relative to `root` into directory paths relative to `root`,
+/// leaving out any matched by `excludes`.
+fn expand_globs(
+    root: &Path,
+    includes: impl IntoIterator<Item = impl AsRef<str>>,
+    excludes: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Result<Vec<PathBuf>, Error> {
+    let excludes = excludes
+        .into_iter()
+        .map(|pattern| glob::Pattern::new(pattern.as_ref().trim_end_matches('/')))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::Glob)?;
+
+    let mut dirs = Vec::new();
+    for pattern in includes {
+        let pattern = pattern.as_ref().trim_end_matches('/');
+        let full_pattern = root.join(pattern);
+        let paths = glob::glob(&full_pattern.to_string_lossy()).map_err(Error::Glob)?;
+        for path in paths {
+            let path = path.map_err(|error| Error::Io(error.into()))?;
+            if !path.is_dir() {
+                continue;
+            }
+            let dir = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            if !excludes.iter().any(|exclude| exclude.matches_path(&dir)) {
+                dirs.push(dir);
+            }
+        }
+    }
+    Ok(dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write;
+
+    #[test]
+    fn test_discover_packages() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            "pnpm-workspace.yaml",
+            "packages:\n  - apps/*\n  - packages/*\n  - '!packages/ignored'\n",
+        );
+        write(
+            root.path(),
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skipped\"]\n",
+        );
+        for dir in [
+            "apps/web",
+            "packages/net-utils",
+            "packages/ignored",
+            "crates/core",
+            "crates/skipped",
+        ] {
+            write(root.path(), &format!("{}/Cargo.toml", dir), "");
+            write(root.path(), &format!("{}/package.json", dir), "");
+        }
+        // Only one of the two manifests, so not a pair.
+        write(root.path(), "apps/docs/package.json", "");
+
+        let dirs = discover_packages(root.path())
+            .unwrap()
+            .into_iter()
+            .map(|package| package.dir)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            dirs,
+            ["apps/web", "crates/core", "packages/net-utils"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn test_sync_workspace() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            "pnpm-workspace.yaml",
+            "packages:\n  - packages/*\n",
+        );
+        for name in ["a", "b", "c"] {
+            write(
+                root.path(),
+                &format!("packages/{}/Cargo.toml", name),
+                &format!("[package]\nname = \"{}\"\nversion = \"1.1.0\"\n", name),
+            );
+            write(
+                root.path(),
+                &format!("packages/{}/package.json", name),
+                "{ \"version\": \"1.0.0\" }",
+            );
+        }
+        write(
+            root.path(),
+            "packages/a/package.json",
+            "{ \"version\": \"oops\" }",
+        );
+
+        let results = sync_workspace(root.path(), None).unwrap().packages;
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].result.is_err());
+        for result in &results[1..] {
+            assert!(!result.result.as_ref().unwrap().is_in_sync());
+            assert_eq!(
+                fs::read_to_string(&result.package.package_json).unwrap(),
+                "{ \"version\": \"1.1.0\" }"
+            );
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::lockfile::{find_lockfiles, sync_lockfiles};
+use crate::manifest::{Manifest, SyncOptions, sync_manifests};
+use crate::{CargoToml, Error, PackageJson};
+
+/// A rewrite that a sync would make to one file, computed without writing it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingChange {
+    /// Path of the file that would change
+    pub path: PathBuf,
+    /// Contents of the file as it is now
+    pub original: String,
+    /// Contents the file would be rewritten to
+    pub patched: String,
+    /// Unified diff from `original` to `patched`. Its headers carry `a/` and `b/`
+    /// prefixes when the path lies under the current directory, and the path as it
+    /// is otherwise
+    pub diff: String,
+}
+
+impl PendingChange {
+    /// Describes rewriting the file at `path` from `original` to `patched`.
+    pub fn new(path: impl Into<PathBuf>, original: String, patched: String) -> Self {
+        let path = path.into();
+        let diff = unified_diff(&path, &original, &patched);
+        Self {
+            path,
+            original,
+            patched,
+            diff,
+        }
+    }
+}
+
+/// Computes the rewrites `sync_contents` would make, keeping each file's original text
+/// alongside the patched text.
+///
+/// # Returns
+///
+/// One `PendingChange` per file that would be rewritten; empty when the versions
+/// already agree.
+///
+/// # Errors
+///
+/// Returns the same errors as `sync_contents`.
+///
+/// # Examples
+///
+/// ```rust
+/// use package_version_sync::dry_run_contents;
+///
+/// let cargo_toml = r#"[package]
+/// name = "my-app"
+/// version = "1.0.0"
+/// "#;
+///
+/// let package_json = r#"{
+///   "name": "my-app",
+///   "version": "1.1.0"
+/// }"#;
+///
+/// let changes = dry_run_contents(cargo_toml, package_json, None)?;
+/// assert!(changes[0].diff.contains("-version = \"1.0.0\""));
+/// assert!(changes[0].diff.contains("+version = \"1.1.0\""));
+/// # Ok::<(), package_version_sync::Error>(())
+/// ```
+pub fn dry_run_contents(
+    cargo_toml_contents: &str,
+    package_json_contents: &str,
+    options: impl Into<SyncOptions>,
+) -> Result<Vec<PendingChange>, Error> {
+    dry_run_manifests(
+        vec![
+            Box::new(CargoToml::from_contents(
+                "Cargo.toml",
+                cargo_toml_contents,
+                None,
+            )?),
+            Box::new(PackageJson::from_contents(
+                "package.json",
+                package_json_contents,
+            )),
+        ],
+        Vec::new(),
+        options,
+    )
+}
+
+/// Computes what `sync_files` would change on disk, without writing anything.
+///
+/// # Errors
+///
+/// Returns the same errors as `sync_files`.
+pub fn dry_run_files(
+    cargo_toml_path: &str,
+    package_json_path: &str,
+    options: impl Into<SyncOptions>,
+) -> Result<Vec<PendingChange>, Error> {
+    let options = options.into();
+    let cargo_toml = CargoToml::read(cargo_toml_path)?;
+    let package_json = PackageJson::read(package_json_path)?;
+    let lockfiles = find_lockfiles(&cargo_toml, &package_json, options.update_package_lock)?;
+    dry_run_manifests(
+        vec![Box::new(cargo_toml), Box::new(package_json)],
+        lockfiles,
+        options,
+    )
+}
+
+fn dry_run_manifests(
+    mut manifests: Vec<Box<dyn Manifest>>,
+    lockfiles: Vec<Box<dyn Manifest>>,
+    options: impl Into<SyncOptions>,
+) -> Result<Vec<PendingChange>, Error> {
+    let originals = manifests
+        .iter()
+        .chain(&lockfiles)
+        .map(|manifest| manifest.contents().to_string())
+        .collect::<Vec<_>>();
+
+    let mut report = sync_manifests(&mut manifests, options)?;
+    sync_lockfiles(lockfiles, &mut manifests, &mut report)?;
+
+    Ok(report
+        .updated_files()
+        .map(|(index, file)| {
+            PendingChange::new(
+                file.path.clone(),
+                originals[index].clone(),
+                manifests[index].contents().to_string(),
+            )
+        })
+        .collect())
+}
+
+fn unified_diff(path: &Path, original: &str, patched: &str) -> String {
+    let (old, new) = match diff_path(path) {
+        Some(path) => (
+            format!("a/{}", path.display()),
+            format!("b/{}", path.display()),
+        ),
+        None => (path.display().to_string(), path.display().to_string()),
+    };
+    similar::TextDiff::from_lines(original, patched)
+        .unified_diff()
+        .header(&old, &new)
+        .to_string()
+}
+
+/// `path` relative to the current directory, for `a/` and `b/` headers that
+/// `git apply` and `patch -p1` accept, or `None` when it lies outside of it.
+fn diff_path(path: &Path) -> Option<PathBuf> {
+    let path = if path.is_absolute() {
+        let current_dir = std::env::current_dir().ok()?;
+        path.strip_prefix(current_dir).ok()?.to_path_buf()
+    } else {
+        path.to_path_buf()
+    };
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        .then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write;
+
+    #[test]
+    fn test_dry_run_in_sync() {
+        let cargo_toml = "[package]\nname = \"test-package\"\nversion = \"1.0.0\"\n";
+        let package_json = "{\n  \"version\": \"1.0.0\"\n}\n";
+
+        let changes = dry_run_contents(cargo_toml, package_json, None).unwrap();
+
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_diff() {
+        let cargo_toml = "[package]\nname = \"test-package\"\nversion = \"1.1.0\"\n";
+        let package_json = "{\n  \"name\": \"test-package\",\n  \"version\": \"1.0.0\"\n}\n";
+
+        let changes = dry_run_contents(cargo_toml, package_json, None).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, PathBuf::from("package.json"));
+        assert_eq!(changes[0].original, package_json);
+        assert_eq!(
+            changes[0].patched,
+            "{\n  \"name\": \"test-package\",\n  \"version\": \"1.1.0\"\n}\n"
+        );
+        assert_eq!(
+            changes[0].diff,
+            r#"--- a/package.json
++++ b/package.json
+@@ -1,4 +1,4 @@
+ {
+   "name": "test-package",
+-  "version": "1.0.0"
++  "version": "1.1.0"
+ }
+"#
+        );
+    }
+
+    #[test]
+    fn test_dry_run_diff_of_lockfile_outside_current_dir() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            "Cargo.toml",
+            "[package]\nname = \"test-package\"\nversion = \"1.1.0\"\n",
+        );
+        write(root.path(), "package.json", "{ \"version\": \"1.1.0\" }");
+        write(
+            root.path(),
+            "Cargo.lock",
+            "[[package]]\nname = \"test-package\"\nversion = \"1.0.0\"\n",
+        );
+        let cargo_lock = root.path().join("Cargo.lock");
+
+        let changes = dry_run_files(
+            &root.path().join("Cargo.toml").to_string_lossy(),
+            &root.path().join("package.json").to_string_lossy(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].diff.starts_with(&format!(
+            "--- {}\n+++ {}\n",
+            cargo_lock.display(),
+            cargo_lock.display()
+        )));
+    }
+
+    #[test]
+    fn test_diff_headers() {
+        let current_dir = std::env::current_dir().unwrap();
+        let cases = [
+            (PathBuf::from("package.json"), "a/package.json"),
+            (PathBuf::from("./Cargo.toml"), "a/./Cargo.toml"),
+            (current_dir.join("Cargo.lock"), "a/Cargo.lock"),
+            (PathBuf::from("../../Cargo.toml"), "../../Cargo.toml"),
+        ];
+
+        for (path, header) in cases {
+            let diff = unified_diff(&path, "1\n", "2\n");
+            assert!(diff.starts_with(&format!("--- {}\n", header)), "{}", diff);
+        }
+    }
+}
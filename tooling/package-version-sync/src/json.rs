@@ -0,0 +1,431 @@
+//! A minimal span-aware JSON reader.
+//!
+//! `serde_json` is used to validate and read package.json, but it forgets where
+//! each value came from. This module re-reads the same text into a tree that keeps
+//! the byte range of every key and value, so edits can splice new text into exactly
+//! one location and leave indentation, key order, and trailing newlines alone.
+
+use std::ops::Range;
+
+/// How deeply arrays and objects may nest, matching `serde_json`'s limit, so a
+/// hostile document fails to parse instead of overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+/// A JSON syntax error found while tokenizing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonSyntaxError {
+    /// Byte offset of the offending character
+    pub offset: usize,
+    /// What the tokenizer expected to find
+    pub message: &'static str,
+}
+
+impl std::fmt::Display for JsonSyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for JsonSyntaxError {}
+
+/// A JSON value together with the byte range it occupies in the source text.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Node {
+    pub span: Range<usize>,
+    pub kind: NodeKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum NodeKind {
+    Object(Vec<Member>),
+    Array(Vec<Node>),
+    String(String),
+    Number,
+    Bool,
+    Null,
+}
+
+/// A key/value pair inside an object.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Member {
+    pub key: String,
+    pub key_span: Range<usize>,
+    pub value: Node,
+}
+
+impl Node {
+    /// Looks up a member of an object by key.
+    ///
+    /// When a key is repeated the last occurrence wins, matching `serde_json`.
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        self.member(key).map(|member| &member.value)
+    }
+
+    /// Looks up a member of an object by key, including its key span.
+    pub fn member(&self, key: &str) -> Option<&Member> {
+        match &self.kind {
+            NodeKind::Object(members) => members.iter().rev().find(|member| member.key == key),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.kind {
+            NodeKind::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a complete JSON document.
+pub(crate) fn parse(source: &str) -> Result<Node, JsonSyntaxError> {
+    let mut parser = Parser {
+        bytes: source.as_bytes(),
+        source,
+        position: 0,
+        depth: 0,
+    };
+    let node = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position != parser.bytes.len() {
+        return Err(parser.error("unexpected trailing characters"));
+    }
+    Ok(node)
+}
+
+/// Encodes `value` as a JSON string literal, quotes included.
+pub(crate) fn quote(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+/// Replaces each span of `source` with the text paired with it. The spans must not
+/// overlap, and may come in any order.
+pub(crate) fn splice(
+    source: &str,
+    edits: impl IntoIterator<Item = (Range<usize>, String)>,
+) -> String {
+    let mut edits = edits.into_iter().collect::<Vec<_>>();
+    // Splice from the end so the earlier spans stay valid.
+    edits.sort_by_key(|(span, _)| std::cmp::Reverse(span.start));
+    let mut spliced = source.to_string();
+    for (span, text) in edits {
+        spliced.replace_range(span, &text);
+    }
+    spliced
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    source: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> JsonSyntaxError {
+        JsonSyntaxError {
+            offset: self.position,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8, message: &'static str) -> Result<(), JsonSyntaxError> {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn value(&mut self) -> Result<Node, JsonSyntaxError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let kind = match self.peek() {
+            Some(b'{') => self.nested(Self::object)?,
+            Some(b'[') => self.nested(Self::array)?,
+            Some(b'"') => NodeKind::String(self.string()?),
+            Some(b't') => self.literal("true", NodeKind::Bool)?,
+            Some(b'f') => self.literal("false", NodeKind::Bool)?,
+            Some(b'n') => self.literal("null", NodeKind::Null)?,
+            Some(b'-' | b'0'..=b'9') => self.number()?,
+            _ => return Err(self.error("expected a value")),
+        };
+        Ok(Node {
+            span: start..self.position,
+            kind,
+        })
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<NodeKind, JsonSyntaxError>,
+    ) -> Result<NodeKind, JsonSyntaxError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.depth += 1;
+        let kind = parse(self);
+        self.depth -= 1;
+        kind
+    }
+
+    fn object(&mut self) -> Result<NodeKind, JsonSyntaxError> {
+        self.position += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(NodeKind::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key_start = self.position;
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected an object key"));
+            }
+            let key = self.string()?;
+            let key_span = key_start..self.position;
+            self.skip_whitespace();
+            self.expect(b':', "expected ':' after object key")?;
+            let value = self.value()?;
+            members.push(Member {
+                key,
+                key_span,
+                value,
+            });
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(NodeKind::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}' in object")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<NodeKind, JsonSyntaxError> {
+        self.position += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(NodeKind::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(NodeKind::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn literal(&mut self, word: &str, kind: NodeKind) -> Result<NodeKind, JsonSyntaxError> {
+        if self.bytes[self.position..].starts_with(word.as_bytes()) {
+            self.position += word.len();
+            Ok(kind)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn number(&mut self) -> Result<NodeKind, JsonSyntaxError> {
+        let start = self.position;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        self.source[start..self.position]
+            .parse::<f64>()
+            .map(|_| NodeKind::Number)
+            .map_err(|_| JsonSyntaxError {
+                offset: start,
+                message: "invalid number",
+            })
+    }
+
+    fn string(&mut self) -> Result<String, JsonSyntaxError> {
+        self.position += 1;
+        let mut value = String::new();
+        loop {
+            let run_start = self.position;
+            while let Some(byte) = self.peek() {
+                if byte == b'"' || byte == b'\\' || byte < 0x20 {
+                    break;
+                }
+                self.position += 1;
+            }
+            value.push_str(&self.source[run_start..self.position]);
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    return Ok(value);
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    value.push(self.escape()?);
+                }
+                Some(_) => return Err(self.error("control character in string")),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, JsonSyntaxError> {
+        let escaped = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.position += 1;
+                let high = self.hex4()?;
+                if !(0xD800..0xDC00).contains(&high) {
+                    return char::from_u32(high)
+                        .ok_or_else(|| self.error("invalid unicode escape"));
+                }
+                if !self.bytes[self.position..].starts_with(b"\\u") {
+                    return Err(self.error("unpaired surrogate in unicode escape"));
+                }
+                self.position += 2;
+                let low = self.hex4()?;
+                if !(0xDC00..0xE000).contains(&low) {
+                    return Err(self.error("unpaired surrogate in unicode escape"));
+                }
+                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                return char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"));
+            }
+            _ => return Err(self.error("invalid escape sequence")),
+        };
+        self.position += 1;
+        Ok(escaped)
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonSyntaxError> {
+        let digits = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .ok_or_else(|| self.error("truncated unicode escape"))?;
+        // `from_str_radix` would also take a leading sign, which JSON does not allow.
+        let code = digits.iter().try_fold(0, |code, &digit| {
+            let value = (digit as char)
+                .to_digit(16)
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+            Ok(code * 16 + value)
+        })?;
+        self.position += 4;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_cover_values() {
+        let source = r#"{ "name": "pkg", "version" : "1.0.0", "n": [1, -2.5e3, true, null] }"#;
+
+        let root = parse(source).unwrap();
+
+        let version = root.member("version").unwrap();
+        assert_eq!(&source[version.key_span.clone()], r#""version""#);
+        assert_eq!(&source[version.value.span.clone()], r#""1.0.0""#);
+        assert_eq!(version.value.as_str(), Some("1.0.0"));
+        assert_eq!(
+            &source[root.get("n").unwrap().span.clone()],
+            "[1, -2.5e3, true, null]"
+        );
+    }
+
+    #[test]
+    fn test_nested_keys_are_not_top_level() {
+        let source = r#"{"publishConfig": {"version": "9.9.9"}, "version": "1.0.0"}"#;
+
+        let root = parse(source).unwrap();
+
+        assert_eq!(root.get("version").unwrap().as_str(), Some("1.0.0"));
+    }
+
+    #[test]
+    fn test_last_duplicate_key_wins() {
+        let source = r#"{"version": "1.0.0", "version": "2.0.0"}"#;
+
+        let root = parse(source).unwrap();
+
+        assert_eq!(root.get("version").unwrap().as_str(), Some("2.0.0"));
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let source = r#"{"kéy": "a\"b\\c\n😀"}"#;
+
+        let root = parse(source).unwrap();
+
+        assert_eq!(root.get("kéy").unwrap().as_str(), Some("a\"b\\c\n😀"));
+    }
+
+    #[test]
+    fn test_splice_keeps_earlier_spans_valid() {
+        let source = r#"{ "a": "1", "b": "2" }"#;
+        let root = parse(source).unwrap();
+        let edits = ["a", "b"].map(|key| (root.get(key).unwrap().span.clone(), quote("10")));
+
+        assert_eq!(splice(source, edits), r#"{ "a": "10", "b": "10" }"#);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(parse("{ invalid json }").is_err());
+        assert!(parse(r#"{"a": 1,}"#).is_err());
+        assert!(parse(r#"{"a": "unterminated}"#).is_err());
+        assert!(parse(r#"{"a": 1} extra"#).is_err());
+    }
+
+    #[test]
+    fn test_unicode_escapes_need_four_hex_digits() {
+        let cases = [
+            (r#""\u+123""#, "invalid unicode escape"),
+            (r#""\u-123""#, "invalid unicode escape"),
+            (r#""\u 123""#, "invalid unicode escape"),
+            (r#""\u12""#, "truncated unicode escape"),
+        ];
+
+        assert_eq!(parse(r#""\u00e9""#).unwrap().as_str(), Some("é"));
+        for (source, message) in cases {
+            assert_eq!(parse(source).unwrap_err().message, message, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_nesting_depth_is_limited() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        let error = parse(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(error.message, "nesting too deep");
+        assert_eq!(error.offset, MAX_DEPTH);
+        assert!(parse(&"{\"a\": ".repeat(100_000)).is_err());
+    }
+}
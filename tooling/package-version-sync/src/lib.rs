@@ -1,17 +1,280 @@
-use std::fs;
-
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+mod align;
+mod bump;
+mod cargo;
+mod check;
+mod discovery;
+mod dry_run;
+mod json;
+mod lockfile;
+mod manifest;
+mod package_json;
+mod report;
+mod requirements;
+#[cfg(test)]
+mod test_support;
+mod workspace_ranges;
+mod write;
+
+pub use align::{
+    AlignChange, AlignOptions, DependencyMismatch, DependencyUsage, align_dependencies,
+    find_mismatches,
+};
+pub use bump::{BumpKind, BumpedContents, bump_contents, bump_manifests, bump_version};
+pub use cargo::{CargoToml, find_workspace_root};
+pub use check::{Drift, check_contents, check_files};
+pub use discovery::{
+    PackageSync, WorkspacePackage, WorkspaceSync, discover_packages, sync_workspace,
+};
+pub use dry_run::{PendingChange, dry_run_contents, dry_run_files};
+pub use json::JsonSyntaxError;
+pub use lockfile::{CargoLock, PackageLock};
+pub use manifest::{
+    BuildMetadataPolicy, Manifest, ManifestChange, ManifestKind, SyncOptions, SyncPolicy,
+    clean_version, sync_manifests,
+};
+pub use package_json::PackageJson;
+pub use report::{FileReport, SyncReason, SyncReport};
+pub use requirements::RequirementChange;
+pub use workspace_ranges::{ResolvedManifest, ResolvedRange, resolve_workspace_ranges};
+pub use write::Transaction;
+
+/// Everything that can go wrong while reading, syncing, or writing manifests.
+///
+/// Errors that wrap another error expose it through `std::error::Error::source`
+/// rather than repeating its message, so walk the chain to report the full cause.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("io error: {0}")]
-    Io(std::io::Error),
-    #[error("version error: {0}")]
-    Version(String),
-    #[error("semver error: {0}")]
-    Semver(semver::Error),
-    #[error("serde error: {0}")]
-    Serde(serde_json::Error),
-    #[error("toml error: {0}")]
-    Toml(toml::de::Error),
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+    /// Cargo.toml has no `[package]` table
+    #[error("cargo toml package not found")]
+    MissingCargoPackage,
+    /// Cargo.toml has no `[package].version`
+    #[error("cargo toml version not found")]
+    MissingCargoVersion,
+    /// package.json has no top-level `version`
+    #[error("package version not found")]
+    MissingPackageJsonVersion,
+    /// package.json is valid JSON, but not an object
+    #[error("package.json is not an object")]
+    NotAnObject,
+    /// Cargo.lock has no entry for the local package
+    #[error("Cargo.lock has no entry for {package}, run cargo to add it")]
+    MissingLockEntry { package: String },
+    /// package-lock.json has no top-level `version`
+    #[error("package-lock.json has no version, run npm to add it")]
+    MissingPackageLockVersion,
+    /// No manifests were given to sync
+    #[error("no manifests to sync")]
+    NoManifests,
+    /// Every manifest lacks a version, so there is nothing to seed from
+    #[error("no manifest has a version to sync")]
+    NoVersionToSync,
+    /// The policy's source of truth is not among the manifests
+    #[error("no {kind} found to use as source of truth")]
+    SourceOfTruthNotFound { kind: ManifestKind },
+    /// The policy's source of truth has no version to seed the others from
+    #[error("{kind} has no version to use as source of truth")]
+    SourceOfTruthUnversioned { kind: ManifestKind },
+    /// `BumpKind::Release` was asked of a version that is not a pre-release
+    #[error("version {version} is not a pre-release")]
+    NotAPrerelease { version: semver::Version },
+    /// The manifests carry different versions, so there is no one version to bump
+    #[error("versions differ ({first} and {other}), sync them before bumping")]
+    OutOfSync { first: String, other: String },
+    /// A version is present but is not a string
+    #[error("version must be a string, found {found_type}")]
+    NonStringVersion { found_type: &'static str },
+    /// Cargo.toml declares `version.workspace = true`, but no workspace root with a
+    /// `[workspace.package].version` was found
+    #[error("cargo toml version is inherited but no workspace version was found")]
+    InheritedVersionUnresolved,
+    #[error("semver error")]
+    Semver(#[from] semver::Error),
+    #[error("serde error")]
+    Serde(#[from] serde_json::Error),
+    #[error("toml error")]
+    Toml(#[from] toml::de::Error),
+    #[error("toml edit error")]
+    TomlEdit(#[from] toml_edit::TomlError),
+    #[error("json syntax error")]
+    JsonSyntax(#[from] JsonSyntaxError),
+    #[error("yaml error")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("glob pattern error")]
+    Glob(#[from] glob::PatternError),
+    #[error("failed to write {}, earlier writes were rolled back", path.display())]
+    WriteFailed {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error(
+        "failed to write {}, and could not restore {}",
+        path.display(),
+        unrestored.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    RollbackFailed {
+        path: PathBuf,
+        source: std::io::Error,
+        unrestored: Vec<PathBuf>,
+    },
+    /// The manifests carry different versions and `SyncPolicy::Strict` refuses to
+    /// pick one
+    #[error(
+        "versions differ ({}), pick a source of truth to resolve",
+        versions.iter().map(|(path, version)| format!("{} has {}", path.display(), version)).collect::<Vec<_>>().join(", ")
+    )]
+    Conflict { versions: Vec<(PathBuf, String)> },
+    /// A forced source of truth would move a file to a lower version
+    #[error("refusing to downgrade {} from {from} to {to}", path.display())]
+    Downgrade {
+        path: PathBuf,
+        from: String,
+        to: String,
+    },
+    /// A `workspace:` range names a package the pnpm workspace does not contain
+    #[error("no workspace package named {name}")]
+    UnknownWorkspacePackage { name: String },
+    /// A range was chosen for a dependency no workspace package.json requests
+    #[error("no workspace package.json depends on {name}")]
+    UnknownDependency { name: String },
+    #[error("patch failed: expected {file} version {expected}, found {found}")]
+    PatchFailed {
+        file: &'static str,
+        expected: String,
+        found: String,
+    },
+    /// Any of the other errors, tied to the file it happened in.
+    #[error("{}", file_position(path, contents.as_deref(), span.as_ref()))]
+    InFile {
+        /// Path of the file, as it was given
+        path: PathBuf,
+        /// Contents of the file, when it could be read
+        contents: Option<String>,
+        /// Byte range in `contents` of the text the error is about
+        span: Option<Range<usize>>,
+        source: Box<Error>,
+    },
+}
+
+/// A 1-based line and column in a file, counting columns in characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    /// Finds the line and column of a byte offset into `contents`.
+    pub fn of(contents: &str, offset: usize) -> Self {
+        let before = &contents[..floor_char_boundary(contents, offset)];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Error {
+    /// Ties the error to the file at `path`, pointing at the offending text when the
+    /// error knows where in `contents` it is. Errors already tied to a file are
+    /// returned unchanged.
+    pub(crate) fn in_file(self, path: &Path, contents: Option<&str>) -> Self {
+        let span = contents.and_then(|contents| self.span_in(contents));
+        self.at(path, contents, span)
+    }
+
+    /// Ties the error to `span` of the file at `path`.
+    pub(crate) fn at(
+        self,
+        path: &Path,
+        contents: Option<&str>,
+        span: Option<Range<usize>>,
+    ) -> Self {
+        match self {
+            Error::InFile { .. } => self,
+            error => Error::InFile {
+                path: path.to_path_buf(),
+                contents: contents.map(str::to_string),
+                span,
+                source: Box::new(error),
+            },
+        }
+    }
+
+    /// Where the error is in its file, when known.
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            Error::InFile {
+                contents: Some(contents),
+                span: Some(span),
+                ..
+            } => Some(Location::of(contents, span.start)),
+            _ => None,
+        }
+    }
+
+    /// Path of the file the error happened in, when known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::InFile { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The error without the file it happened in.
+    pub fn into_inner(self) -> Error {
+        match self {
+            Error::InFile { source, .. } => *source,
+            error => error,
+        }
+    }
+
+    fn span_in(&self, contents: &str) -> Option<Range<usize>> {
+        let offset = match self {
+            Error::Toml(error) => return error.span(),
+            Error::TomlEdit(error) => return error.span(),
+            Error::JsonSyntax(error) => error.offset,
+            Error::Serde(error) if error.line() > 0 => {
+                let line_start = contents
+                    .split_inclusive('\n')
+                    .take(error.line() - 1)
+                    .map(str::len)
+                    .sum::<usize>();
+                line_start + error.column().saturating_sub(1)
+            }
+            Error::Yaml(error) => error.location()?.index(),
+            _ => return None,
+        };
+        let offset = floor_char_boundary(contents, offset);
+        let end = contents[offset..]
+            .chars()
+            .next()
+            .map_or(offset, |c| offset + c.len_utf8());
+        Some(offset..end)
+    }
+}
+
+fn floor_char_boundary(contents: &str, offset: usize) -> usize {
+    let mut offset = offset.min(contents.len());
+    while !contents.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn file_position(path: &Path, contents: Option<&str>, span: Option<&Range<usize>>) -> String {
+    match contents.zip(span) {
+        Some((contents, span)) => {
+            let location = Location::of(contents, span.start);
+            format!("{}:{}:{}", path.display(), location.line, location.column)
+        }
+        None => path.display().to_string(),
+    }
 }
 
 /// Specifies which file should be the authoritative source for version numbers.
@@ -23,39 +286,6 @@ pub enum SourceOfTruth {
     PackageJson,
 }
 
-/// Result of syncing version numbers between Cargo.toml and package.json files.
-#[derive(Clone, Debug)]
-pub enum SyncContentsResult {
-    /// No changes needed - versions already in sync
-    NoChanges,
-    /// Cargo.toml was updated
-    UpdatedCargoToml(String),
-    /// package.json was updated
-    UpdatedPackageJson(String),
-}
-
-fn patch_cargo_toml_version(
-    cargo_toml_contents: &str,
-    old_version: &str,
-    new_version: &str,
-) -> String {
-    cargo_toml_contents.replace(
-        format!("version = \"{}\"", old_version).as_str(),
-        format!("version = \"{}\"", new_version).as_str(),
-    )
-}
-
-fn patch_package_json_version(
-    package_json_contents: &str,
-    old_version: &str,
-    new_version: &str,
-) -> String {
-    package_json_contents.replace(
-        format!("\"version\": \"{}\"", old_version).as_str(),
-        format!("\"version\": \"{}\"", new_version).as_str(),
-    )
-}
-
 /// Syncs version numbers between Cargo.toml and package.json files.
 ///
 /// Compares versions and updates the older one to match the newer. Supports semantic
@@ -65,26 +295,34 @@ fn patch_package_json_version(
 ///
 /// * `cargo_toml_contents` - Raw Cargo.toml file contents
 /// * `package_json_contents` - Raw package.json file contents  
-/// * `source_of_truth` - Force which file's version to use (None = use newer version)
+/// * `options` - Force which file's version to use (None = use newer version), or
+///   full `SyncOptions`
 ///
 /// # Returns
 ///
-/// * `NoChanges` - Versions already in sync
-/// * `UpdatedCargoToml(String)` - Cargo.toml was updated
-/// * `UpdatedPackageJson(String)` - package.json was updated
+/// A `SyncReport` listing Cargo.toml then package.json, each with its previous and
+/// new version and, when it was rewritten, its patched contents.
 ///
 /// # Errors
 ///
 /// * `Error::Serde` - Invalid JSON in package.json
+/// * `Error::JsonSyntax` - package.json could not be tokenized for span-exact editing
 /// * `Error::Toml` - Invalid TOML in Cargo.toml
-/// * `Error::Version` - Missing or malformed version fields
+/// * `Error::TomlEdit` - Cargo.toml could not be loaded for format-preserving editing
+/// * `Error::MissingCargoVersion` - Cargo.toml has no `[package].version`
+/// * `Error::MissingPackageJsonVersion` - package.json has no `version`
+/// * `Error::NonStringVersion` - A version is not a string
 /// * `Error::Semver` - Invalid semantic version strings
+/// * `Error::Conflict` - The versions differ and the policy is `SyncPolicy::Strict`
+/// * `Error::Downgrade` - The source of truth is older than the other file and
+///   `allow_downgrade` is off
+/// * `Error::PatchFailed` - The rewritten file did not carry the new version
 ///
 /// # Examples
 ///
 /// ## Basic usage - let the function decide which version is newer
 /// ```rust
-/// use package_version_sync::{sync_contents, SourceOfTruth, SyncContentsResult};
+/// use package_version_sync::{sync_contents, ManifestKind, SourceOfTruth};
 ///
 /// let cargo_toml = r#"[package]
 /// name = "my-app"
@@ -97,20 +335,19 @@ fn patch_package_json_version(
 ///   "version": "1.1.0"
 /// }"#;
 ///
-/// let result = sync_contents(cargo_toml, package_json, None)?;
-/// match result {
-///     SyncContentsResult::UpdatedCargoToml(contents) => {
-///         // Cargo.toml was updated to version "1.1.0"
-///         println!("Updated Cargo.toml: {}", contents);
-///     }
-///     _ => {}
+/// let report = sync_contents(cargo_toml, package_json, None)?;
+/// if let Some(contents) = report.updated_contents(ManifestKind::CargoToml) {
+///     // Cargo.toml was updated to version "1.1.0"
+///     println!("Updated Cargo.toml: {}", contents);
 /// }
 /// # Ok::<(), package_version_sync::Error>(())
 /// ```
 ///
-/// ## Force package.json to be the source of truth
+/// ## Force package.json to be the source of truth, even over a newer Cargo.toml
 /// ```rust
-/// use package_version_sync::{sync_contents, SourceOfTruth, SyncContentsResult};
+/// use package_version_sync::{
+///     sync_contents, ManifestKind, SourceOfTruth, SyncOptions, SyncPolicy, SyncReason,
+/// };
 ///
 /// let cargo_toml = r#"[package]
 /// name = "my-app"
@@ -123,20 +360,34 @@ fn patch_package_json_version(
 ///   "version": "1.0.0"
 /// }"#;
 ///
-/// let result = sync_contents(cargo_toml, package_json, Some(SourceOfTruth::PackageJson))?;
-/// match result {
-///     SyncContentsResult::UpdatedCargoToml(contents) => {
-///         // Cargo.toml was updated to version "1.0.0"
-///         println!("Updated Cargo.toml: {}", contents);
-///     }
-///     _ => {}
+/// let options = SyncOptions {
+///     policy: SyncPolicy::SourceOfTruth(SourceOfTruth::PackageJson),
+///     allow_downgrade: true,
+///     ..SyncOptions::default()
+/// };
+/// let report = sync_contents(cargo_toml, package_json, options)?;
+/// assert!(matches!(report.reason, SyncReason::SourceOfTruth { .. }));
+/// if let Some(contents) = report.updated_contents(ManifestKind::CargoToml) {
+///     // Cargo.toml was updated to version "1.0.0"
+///     println!("Updated Cargo.toml: {}", contents);
 /// }
 /// # Ok::<(), package_version_sync::Error>(())
 /// ```
 ///
+/// ## Refuse to pick a version when the files disagree
+/// ```rust
+/// use package_version_sync::{sync_contents, Error, SyncPolicy};
+///
+/// let cargo_toml = "[package]\nname = \"my-app\"\nversion = \"1.0.0\"\n";
+/// let package_json = r#"{ "name": "my-app", "version": "2.0.0" }"#;
+///
+/// let result = sync_contents(cargo_toml, package_json, SyncPolicy::Strict);
+/// assert!(matches!(result, Err(Error::Conflict { .. })));
+/// ```
+///
 /// ## Handle pre-release versions
 /// ```rust
-/// use package_version_sync::{sync_contents, SyncContentsResult};
+/// use package_version_sync::{sync_contents, ManifestKind};
 ///
 /// let cargo_toml = r#"[package]
 /// name = "my-app"
@@ -149,20 +400,19 @@ fn patch_package_json_version(
 ///   "version": "1.0.0"
 /// }"#;
 ///
-/// let result = sync_contents(cargo_toml, package_json, None)?;
-/// match result {
-///     SyncContentsResult::UpdatedCargoToml(contents) => {
-///         // Pre-release version is considered older, so cargo.toml gets updated to "1.0.0"
-///         println!("Updated Cargo.toml: {}", contents);
-///     }
-///     _ => {}
+/// let report = sync_contents(cargo_toml, package_json, None)?;
+/// if let Some(contents) = report.updated_contents(ManifestKind::CargoToml) {
+///     // Pre-release version is considered older, so cargo.toml gets updated to "1.0.0"
+///     println!("Updated Cargo.toml: {}", contents);
 /// }
 /// # Ok::<(), package_version_sync::Error>(())
 /// ```
 ///
 /// ## Error handling
 /// ```rust
-/// use package_version_sync::{sync_contents, SyncContentsResult};
+/// use std::error::Error;
+///
+/// use package_version_sync::sync_contents;
 ///
 /// let cargo_toml = r#"[package]
 /// name = "my-app"
@@ -176,110 +426,143 @@ fn patch_package_json_version(
 /// }"#;
 ///
 /// match sync_contents(cargo_toml, package_json, None) {
-///     Ok(SyncContentsResult::NoChanges) => println!("Versions already synchronized"),
-///     Ok(SyncContentsResult::UpdatedCargoToml(_)) => println!("Cargo.toml was updated"),
-///     Ok(SyncContentsResult::UpdatedPackageJson(_)) => println!("package.json was updated"),
-///     Err(package_version_sync::Error::Version(msg)) => {
-///         println!("Parse error: {}", msg);
+///     Ok(report) if report.is_in_sync() => println!("Versions already synchronized"),
+///     Ok(report) => {
+///         for (_, file) in report.updated_files() {
+///             println!("{} was updated to {}", file.kind, file.new_version);
+///         }
+///     }
+///     Err(e) => {
+///         // `Error::InFile` displays only where the error is; what went wrong is
+///         // its source.
+///         let mut message = e.to_string();
+///         let mut source = e.source();
+///         while let Some(error) = source {
+///             message = format!("{}: {}", message, error);
+///             source = error.source();
+///         }
+///         match e.location() {
+///             Some(location) => println!("Error at line {}: {}", location.line, message),
+///             None => println!("Error: {}", message),
+///         }
 ///     }
-///     Err(e) => println!("Other error: {}", e),
 /// }
 /// ```
 pub fn sync_contents(
     cargo_toml_contents: &str,
     package_json_contents: &str,
-    source_of_truth: Option<SourceOfTruth>,
-) -> Result<SyncContentsResult, Error> {
-    let parsed_package_json =
-        serde_json::from_str::<serde_json::Value>(package_json_contents).map_err(Error::Serde)?;
-    let package_json_version = parsed_package_json
-        .get("version")
-        .and_then(|version| version.as_str())
-        .ok_or_else(|| Error::Version("package version not found".to_string()))?;
-    let package_json_version_semver =
-        semver::Version::parse(package_json_version).map_err(Error::Semver)?;
-
-    let parsed_cargo_toml =
-        toml::from_str::<toml::Table>(cargo_toml_contents).map_err(Error::Toml)?;
-    let cargo_toml_version = parsed_cargo_toml
-        .get("package")
-        .and_then(|package| package.get("version"))
-        .and_then(|version| version.as_str())
-        .ok_or_else(|| Error::Version("cargo toml version not found".to_string()))?;
-    let cargo_toml_version_semver =
-        semver::Version::parse(cargo_toml_version).map_err(Error::Semver)?;
-
-    if cargo_toml_version_semver.eq(&package_json_version_semver) {
-        return Ok(SyncContentsResult::NoChanges);
-    }
-
-    match source_of_truth {
-        Some(SourceOfTruth::PackageJson) => {
-            return Ok(SyncContentsResult::UpdatedCargoToml(
-                patch_cargo_toml_version(
-                    cargo_toml_contents,
-                    cargo_toml_version,
-                    package_json_version,
-                )
-                .to_string(),
-            ));
-        }
-        Some(SourceOfTruth::CargoToml) => {
-            return Ok(SyncContentsResult::UpdatedPackageJson(
-                patch_package_json_version(
-                    package_json_contents,
-                    package_json_version,
-                    cargo_toml_version,
-                )
-                .to_string(),
-            ));
-        }
-        None => {}
-    }
-
-    if package_json_version_semver.gt(&cargo_toml_version_semver) {
-        return Ok(SyncContentsResult::UpdatedCargoToml(
-            patch_cargo_toml_version(
-                cargo_toml_contents,
-                cargo_toml_version,
-                package_json_version,
-            )
-            .to_string(),
-        ));
-    }
-
-    if cargo_toml_version_semver.gt(&package_json_version_semver) {
-        return Ok(SyncContentsResult::UpdatedPackageJson(
-            patch_package_json_version(
-                package_json_contents,
-                package_json_version,
-                cargo_toml_version,
-            )
-            .to_string(),
-        ));
-    }
+    options: impl Into<SyncOptions>,
+) -> Result<SyncReport, Error> {
+    sync_contents_with_workspace(cargo_toml_contents, None, package_json_contents, options)
+}
 
-    Ok(SyncContentsResult::NoChanges)
+/// Syncs version numbers between a Cargo.toml that may inherit its version from a
+/// workspace and a package.json file.
+///
+/// Behaves like `sync_contents`, except that a crate declaring
+/// `version.workspace = true` is resolved against `[workspace.package].version`,
+/// taken from the crate's own manifest when it is the workspace root or from
+/// `workspace_cargo_toml_contents` otherwise. Updates to an inherited version are
+/// written to the manifest that declares it.
+///
+/// # Parameters
+///
+/// * `cargo_toml_contents` - Raw Cargo.toml file contents of the crate
+/// * `workspace_cargo_toml_contents` - Raw contents of the workspace root Cargo.toml, if any
+/// * `package_json_contents` - Raw package.json file contents
+/// * `options` - Force which file's version to use (None = use newer version), or
+///   full `SyncOptions`
+///
+/// # Returns
+///
+/// A `SyncReport` like `sync_contents` returns. An inherited version is reported
+/// as `ManifestKind::WorkspaceCargoToml`, with the workspace root's patched contents.
+///
+/// # Errors
+///
+/// Returns the same errors as `sync_contents`, plus
+/// `Error::InheritedVersionUnresolved` when the version is inherited but no
+/// workspace manifest declaring `[workspace.package].version` is available.
+///
+/// # Examples
+///
+/// ```rust
+/// use package_version_sync::{sync_contents_with_workspace, ManifestKind};
+///
+/// let workspace_cargo_toml = r#"[workspace]
+/// members = ["crates/my-app"]
+///
+/// [workspace.package]
+/// version = "1.0.0"
+/// "#;
+///
+/// let cargo_toml = r#"[package]
+/// name = "my-app"
+/// version.workspace = true
+/// "#;
+///
+/// let package_json = r#"{
+///   "name": "my-app",
+///   "version": "1.1.0"
+/// }"#;
+///
+/// let report =
+///     sync_contents_with_workspace(cargo_toml, Some(workspace_cargo_toml), package_json, None)?;
+/// assert!(report.updated_contents(ManifestKind::WorkspaceCargoToml).is_some());
+/// # Ok::<(), package_version_sync::Error>(())
+/// ```
+pub fn sync_contents_with_workspace(
+    cargo_toml_contents: &str,
+    workspace_cargo_toml_contents: Option<&str>,
+    package_json_contents: &str,
+    options: impl Into<SyncOptions>,
+) -> Result<SyncReport, Error> {
+    let mut manifests: Vec<Box<dyn Manifest>> = vec![
+        Box::new(CargoToml::from_contents(
+            "Cargo.toml",
+            cargo_toml_contents,
+            workspace_cargo_toml_contents
+                .map(|contents| (PathBuf::from("Cargo.toml"), contents.to_string())),
+        )?),
+        Box::new(PackageJson::from_contents(
+            "package.json",
+            package_json_contents,
+        )),
+    ];
+
+    sync_manifests(&mut manifests, options)
 }
 
 /// Syncs version numbers between Cargo.toml and package.json files on disk.
 ///
-/// Reads files, syncs versions using `sync_contents`, and writes updates back.
+/// Reads files, syncs versions using `sync_manifests`, and atomically writes updates
+/// back, keeping each file's permissions. When the crate inherits its version with
+/// `version.workspace = true`, the nearest ancestor Cargo.toml declaring
+/// `[workspace]` is read and updated instead.
+///
+/// When a Cargo.lock records the crate, its entry is brought to the Cargo.toml
+/// version as well and the lockfile is listed after the manifests in the report. For
+/// an inherited version, the entries of every member inheriting it are updated
+/// together. With `update_package_lock`,
+/// the package-lock.json next to package.json follows package.json the same way.
 ///
 /// # Parameters
 ///
 /// * `cargo_toml_path` - Path to Cargo.toml file
 /// * `package_json_path` - Path to package.json file
-/// * `source_of_truth` - Force which file's version to use (None = use newer version)
+/// * `options` - Force which file's version to use (None = use newer version), or
+///   full `SyncOptions`
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on success. Only writes files when updates are needed.
+/// The `SyncReport` of the sync. Only files it reports as updated are written.
 ///
 /// # Errors
 ///
 /// Returns errors for file I/O issues, invalid JSON/TOML, missing version fields,
-/// or invalid semantic version strings.
+/// or invalid semantic version strings. Updated files are written in one
+/// `Transaction`, so a failed write returns `Error::WriteFailed` naming the file
+/// after putting back any file already written.
 ///
 /// # Examples
 ///
@@ -296,49 +579,35 @@ pub fn sync_contents(
 pub fn sync_files(
     cargo_toml_path: &str,
     package_json_path: &str,
-    source_of_truth: Option<SourceOfTruth>,
-) -> Result<(), Error> {
-    match sync_contents(
-        &fs::read_to_string(cargo_toml_path).map_err(Error::Io)?,
-        &fs::read_to_string(package_json_path).map_err(Error::Io)?,
-        source_of_truth,
-    )? {
-        SyncContentsResult::UpdatedCargoToml(cargo_toml_contents) => {
-            fs::write(cargo_toml_path, cargo_toml_contents).map_err(Error::Io)?;
-        }
-        SyncContentsResult::UpdatedPackageJson(package_json_contents) => {
-            fs::write(package_json_path, package_json_contents).map_err(Error::Io)?;
-        }
-        SyncContentsResult::NoChanges => (),
+    options: impl Into<SyncOptions>,
+) -> Result<SyncReport, Error> {
+    let options = options.into();
+    let cargo_toml = CargoToml::read(cargo_toml_path)?;
+    let package_json = PackageJson::read(package_json_path)?;
+    let lockfiles =
+        lockfile::find_lockfiles(&cargo_toml, &package_json, options.update_package_lock)?;
+    let mut manifests: Vec<Box<dyn Manifest>> = vec![Box::new(cargo_toml), Box::new(package_json)];
+
+    let mut report = sync_manifests(&mut manifests, options)?;
+    lockfile::sync_lockfiles(lockfiles, &mut manifests, &mut report)?;
+    let mut transaction = Transaction::new();
+    for (index, _) in report.updated_files() {
+        transaction.stage_manifest(manifests[index].as_ref());
     }
+    transaction.commit()?;
 
-    Ok(())
+    Ok(report)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{create_cargo_toml, create_package_json};
 
-    fn create_cargo_toml(version: &str) -> String {
-        format!(
-            r#"[package]
-name = "test-package"
-version = "{}"
-edition = "2021"
-"#,
-            version
-        )
-    }
-
-    fn create_package_json(version: &str) -> String {
-        format!(
-            r#"{{
-  "name": "test-package",
-  "version": "{}",
-  "type": "module"
-}}"#,
-            version
-        )
+    fn expect_updated(report: &SyncReport, kind: ManifestKind) -> &str {
+        report
+            .updated_contents(kind)
+            .unwrap_or_else(|| panic!("Expected {} to be updated", kind))
     }
 
     #[test]
@@ -348,12 +617,10 @@ edition = "2021"
 
         let result = sync_contents(&cargo_toml, &package_json, None).unwrap();
 
-        match result {
-            SyncContentsResult::NoChanges => {
-                // This is expected when versions are equal
-            }
-            _ => panic!("Expected NoChanges result when versions are equal"),
-        }
+        assert!(
+            result.is_in_sync(),
+            "Expected no updates when versions are equal"
+        );
     }
 
     #[test]
@@ -364,12 +631,10 @@ edition = "2021"
         let result = sync_contents(&cargo_toml, &package_json, None).unwrap();
 
         let expected_cargo_toml = create_cargo_toml("1.2.4");
-        match result {
-            SyncContentsResult::UpdatedCargoToml(updated_contents) => {
-                assert_eq!(updated_contents, expected_cargo_toml);
-            }
-            _ => panic!("Expected UpdatedCargoToml result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::CargoToml),
+            expected_cargo_toml
+        );
     }
 
     #[test]
@@ -380,12 +645,10 @@ edition = "2021"
         let result = sync_contents(&cargo_toml, &package_json, None).unwrap();
 
         let expected_package_json = create_package_json("1.2.4");
-        match result {
-            SyncContentsResult::UpdatedPackageJson(updated_contents) => {
-                assert_eq!(updated_contents, expected_package_json);
-            }
-            _ => panic!("Expected UpdatedPackageJson result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::PackageJson),
+            expected_package_json
+        );
     }
 
     #[test]
@@ -393,16 +656,19 @@ edition = "2021"
         let cargo_toml = create_cargo_toml("1.2.4");
         let package_json = create_package_json("1.2.3");
 
-        let result =
-            sync_contents(&cargo_toml, &package_json, Some(SourceOfTruth::PackageJson)).unwrap();
+        let options = SyncOptions {
+            policy: SyncPolicy::SourceOfTruth(SourceOfTruth::PackageJson),
+            allow_downgrade: true,
+            ..SyncOptions::default()
+        };
+
+        let result = sync_contents(&cargo_toml, &package_json, options).unwrap();
 
         let expected_cargo_toml = create_cargo_toml("1.2.3");
-        match result {
-            SyncContentsResult::UpdatedCargoToml(updated_contents) => {
-                assert_eq!(updated_contents, expected_cargo_toml);
-            }
-            _ => panic!("Expected UpdatedCargoToml result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::CargoToml),
+            expected_cargo_toml
+        );
     }
 
     #[test]
@@ -410,16 +676,34 @@ edition = "2021"
         let cargo_toml = create_cargo_toml("1.2.3");
         let package_json = create_package_json("1.2.4");
 
-        let result =
-            sync_contents(&cargo_toml, &package_json, Some(SourceOfTruth::CargoToml)).unwrap();
+        let options = SyncOptions {
+            policy: SyncPolicy::SourceOfTruth(SourceOfTruth::CargoToml),
+            allow_downgrade: true,
+            ..SyncOptions::default()
+        };
+
+        let result = sync_contents(&cargo_toml, &package_json, options).unwrap();
 
         let expected_package_json = create_package_json("1.2.3");
-        match result {
-            SyncContentsResult::UpdatedPackageJson(updated_contents) => {
-                assert_eq!(updated_contents, expected_package_json);
-            }
-            _ => panic!("Expected UpdatedPackageJson result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::PackageJson),
+            expected_package_json
+        );
+    }
+
+    #[test]
+    fn test_source_of_truth_rejects_downgrade() {
+        let cargo_toml = create_cargo_toml("2.0.0");
+        let package_json = create_package_json("1.0.0");
+
+        let result = sync_contents(&cargo_toml, &package_json, Some(SourceOfTruth::PackageJson));
+
+        let error = result.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "refusing to downgrade Cargo.toml from 2.0.0 to 1.0.0"
+        );
+        assert!(matches!(error, Error::Downgrade { .. }));
     }
 
     #[test]
@@ -430,12 +714,10 @@ edition = "2021"
         let result = sync_contents(&cargo_toml, &package_json, None).unwrap();
 
         let expected_package_json = create_package_json("2.0.0");
-        match result {
-            SyncContentsResult::UpdatedPackageJson(updated_contents) => {
-                assert_eq!(updated_contents, expected_package_json);
-            }
-            _ => panic!("Expected UpdatedPackageJson result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::PackageJson),
+            expected_package_json
+        );
     }
 
     #[test]
@@ -446,12 +728,10 @@ edition = "2021"
         let result = sync_contents(&cargo_toml, &package_json, None).unwrap();
 
         let expected_package_json = create_package_json("1.1.0");
-        match result {
-            SyncContentsResult::UpdatedPackageJson(updated_contents) => {
-                assert_eq!(updated_contents, expected_package_json);
-            }
-            _ => panic!("Expected UpdatedPackageJson result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::PackageJson),
+            expected_package_json
+        );
     }
 
     #[test]
@@ -462,12 +742,10 @@ edition = "2021"
         let result = sync_contents(&cargo_toml, &package_json, None).unwrap();
 
         let expected_package_json = create_package_json("1.0.1");
-        match result {
-            SyncContentsResult::UpdatedPackageJson(updated_contents) => {
-                assert_eq!(updated_contents, expected_package_json);
-            }
-            _ => panic!("Expected UpdatedPackageJson result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::PackageJson),
+            expected_package_json
+        );
     }
 
     #[test]
@@ -478,12 +756,10 @@ edition = "2021"
         let result = sync_contents(&cargo_toml, &package_json, None).unwrap();
 
         let expected_cargo_toml = create_cargo_toml("1.0.0");
-        match result {
-            SyncContentsResult::UpdatedCargoToml(updated_contents) => {
-                assert_eq!(updated_contents, expected_cargo_toml);
-            }
-            _ => panic!("Expected UpdatedCargoToml result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::CargoToml),
+            expected_cargo_toml
+        );
     }
 
     #[test]
@@ -494,12 +770,42 @@ edition = "2021"
         let result = sync_contents(&cargo_toml, &package_json, None).unwrap();
 
         let expected_package_json = create_package_json("1.0.0+build.1");
-        match result {
-            SyncContentsResult::UpdatedPackageJson(updated_contents) => {
-                assert_eq!(updated_contents, expected_package_json);
-            }
-            _ => panic!("Expected UpdatedPackageJson result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::PackageJson),
+            expected_package_json
+        );
+    }
+
+    #[test]
+    fn test_build_metadata_ignored() {
+        let cargo_toml = create_cargo_toml("1.0.0+build.1");
+        let package_json = create_package_json("1.0.0");
+        let options = SyncOptions {
+            build_metadata: BuildMetadataPolicy::Ignore,
+            ..SyncOptions::default()
+        };
+
+        let result = sync_contents(&cargo_toml, &package_json, options).unwrap();
+
+        assert!(result.is_in_sync());
+    }
+
+    #[test]
+    fn test_build_metadata_stripped_from_package_json() {
+        let cargo_toml = create_cargo_toml("1.2.0+abc123");
+        let package_json = create_package_json("1.1.0");
+        let options = SyncOptions {
+            build_metadata: BuildMetadataPolicy::Strip(SourceOfTruth::PackageJson),
+            ..SyncOptions::default()
+        };
+
+        let result = sync_contents(&cargo_toml, &package_json, options).unwrap();
+
+        assert_eq!(result.updated_files().count(), 1);
+        assert_eq!(
+            expect_updated(&result, ManifestKind::PackageJson),
+            create_package_json("1.2.0")
+        );
     }
 
     #[test]
@@ -509,13 +815,57 @@ edition = "2021"
 
         let result = sync_contents(&cargo_toml, package_json, None);
 
-        assert!(result.is_err());
-        match result.unwrap_err() {
+        let error = result.unwrap_err();
+        assert_eq!(error.location(), Some(Location { line: 1, column: 3 }));
+        match error.into_inner() {
             Error::Serde(_) => {}
             _ => panic!("Expected Serde error"),
         }
     }
 
+    #[test]
+    fn test_location_counts_characters() {
+        let contents = "{\n  \"name\": \"ünïcode\",\n  \"version\": 1\n}";
+
+        assert_eq!(Location::of(contents, 0), Location { line: 1, column: 1 });
+        assert_eq!(
+            Location::of(contents, contents.find("1").unwrap()),
+            Location {
+                line: 3,
+                column: 14
+            }
+        );
+        assert_eq!(
+            Location::of(contents, contents.find("code").unwrap()),
+            Location {
+                line: 2,
+                column: 15
+            }
+        );
+    }
+
+    #[test]
+    fn test_error_source_chain() {
+        let cargo_toml = create_cargo_toml("1.0.0");
+        let package_json = r#"{ "version": "not-a-version" }"#;
+
+        let error = sync_contents(&cargo_toml, package_json, None).unwrap_err();
+
+        let messages = std::iter::successors(Some(&error as &dyn std::error::Error), |error| {
+            error.source()
+        })
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "package.json:1:14",
+                "semver error",
+                "unexpected character 'n' while parsing major version number",
+            ]
+        );
+    }
+
     #[test]
     fn test_invalid_cargo_toml() {
         let cargo_toml = "[package]\ninvalid toml";
@@ -524,7 +874,7 @@ edition = "2021"
         let result = sync_contents(cargo_toml, &package_json, None);
 
         assert!(result.is_err());
-        match result.unwrap_err() {
+        match result.unwrap_err().into_inner() {
             Error::Toml(_) => {}
             _ => panic!("Expected Toml error"),
         }
@@ -540,9 +890,9 @@ edition = "2021"
         let result = sync_contents(&cargo_toml, package_json, None);
 
         assert!(result.is_err());
-        match result.unwrap_err() {
-            Error::Version(msg) => assert_eq!(msg, "package version not found"),
-            _ => panic!("Expected Parse error"),
+        match result.unwrap_err().into_inner() {
+            Error::MissingPackageJsonVersion => {}
+            _ => panic!("Expected MissingPackageJsonVersion error"),
         }
     }
 
@@ -557,12 +907,36 @@ edition = "2021"
         let result = sync_contents(cargo_toml, &package_json, None);
 
         assert!(result.is_err());
-        match result.unwrap_err() {
-            Error::Version(msg) => assert_eq!(msg, "cargo toml version not found"),
-            _ => panic!("Expected Parse error"),
+        match result.unwrap_err().into_inner() {
+            Error::MissingCargoVersion => {}
+            _ => panic!("Expected MissingCargoVersion error"),
         }
     }
 
+    #[test]
+    fn test_seed_missing_cargo_toml_version() {
+        let cargo_toml = r#"[package]
+name = "test-package"
+edition = "2021"
+"#;
+        let package_json = create_package_json("1.0.0");
+        let options = SyncOptions {
+            seed_missing_versions: true,
+            ..SyncOptions::default()
+        };
+
+        let result = sync_contents(cargo_toml, &package_json, options).unwrap();
+
+        assert_eq!(
+            expect_updated(&result, ManifestKind::CargoToml),
+            r#"[package]
+name = "test-package"
+version = "1.0.0"
+edition = "2021"
+"#
+        );
+    }
+
     #[test]
     fn test_invalid_semver_package_json() {
         let cargo_toml = create_cargo_toml("1.0.0");
@@ -573,8 +947,16 @@ edition = "2021"
 
         let result = sync_contents(&cargo_toml, package_json, None);
 
-        assert!(result.is_err());
-        match result.unwrap_err() {
+        let error = result.unwrap_err();
+        assert_eq!(error.path(), Some(Path::new("package.json")));
+        assert_eq!(
+            error.location(),
+            Some(Location {
+                line: 3,
+                column: 14
+            })
+        );
+        match error.into_inner() {
             Error::Semver(_) => {}
             _ => panic!("Expected Semver error"),
         }
@@ -591,8 +973,16 @@ edition = "2021"
 
         let result = sync_contents(cargo_toml, &package_json, None);
 
-        assert!(result.is_err());
-        match result.unwrap_err() {
+        let error = result.unwrap_err();
+        assert_eq!(error.path(), Some(Path::new("Cargo.toml")));
+        assert_eq!(
+            error.location(),
+            Some(Location {
+                line: 3,
+                column: 11
+            })
+        );
+        match error.into_inner() {
             Error::Semver(_) => {}
             _ => panic!("Expected Semver error"),
         }
@@ -607,9 +997,14 @@ edition = "2021"
 
 [dependencies]
 serde = "1.0"
+foo = { version = "1.0.0" }
+
+[dependencies.bar]
+version = "1.0.0"
 
 [dev-dependencies]
 tokio = "1.0"
+baz = { version = "1.0.0", features = ["full"] }
 "#;
         let package_json = create_package_json("2.0.0");
 
@@ -622,16 +1017,49 @@ edition = "2021"
 
 [dependencies]
 serde = "1.0"
+foo = { version = "1.0.0" }
+
+[dependencies.bar]
+version = "1.0.0"
 
 [dev-dependencies]
 tokio = "1.0"
+baz = { version = "1.0.0", features = ["full"] }
 "#;
-        match result {
-            SyncContentsResult::UpdatedCargoToml(updated_contents) => {
-                assert_eq!(updated_contents, expected_cargo_toml);
-            }
-            _ => panic!("Expected UpdatedCargoToml result"),
-        }
+        assert_eq!(
+            expect_updated(&result, ManifestKind::CargoToml),
+            expected_cargo_toml
+        );
+    }
+
+    #[test]
+    fn test_cargo_toml_comments_and_whitespace_preserved() {
+        let cargo_toml = r#"# The web tooling crate
+[package]
+name    = "test-package"
+version   =   "1.0.0"   # kept in step with package.json
+edition = "2021"
+
+[workspace.package]
+version = "1.0.0"
+"#;
+        let package_json = create_package_json("1.1.0");
+
+        let result = sync_contents(cargo_toml, &package_json, None).unwrap();
+
+        let expected_cargo_toml = r#"# The web tooling crate
+[package]
+name    = "test-package"
+version   =   "1.1.0"   # kept in step with package.json
+edition = "2021"
+
+[workspace.package]
+version = "1.0.0"
+"#;
+        assert_eq!(
+            expect_updated(&result, ManifestKind::CargoToml),
+            expected_cargo_toml
+        );
     }
 
     #[test]
@@ -652,11 +1080,173 @@ tokio = "1.0"
         let result = sync_contents(&cargo_toml, package_json, None).unwrap();
 
         let expected_cargo_toml = create_cargo_toml("2.0.0");
-        match result {
-            SyncContentsResult::UpdatedCargoToml(updated_contents) => {
-                assert_eq!(updated_contents, expected_cargo_toml);
-            }
-            _ => panic!("Expected UpdatedCargoToml result"),
+        assert_eq!(
+            expect_updated(&result, ManifestKind::CargoToml),
+            expected_cargo_toml
+        );
+    }
+
+    #[test]
+    fn test_package_json_nested_versions_untouched() {
+        let cargo_toml = create_cargo_toml("1.1.0");
+        let package_json = r#"{
+  "name": "test-package",
+  "version": "1.0.0",
+  "publishConfig": {
+    "version": "1.0.0"
+  },
+  "volta": { "node": "22.0.0", "version": "1.0.0" }
+}
+"#;
+
+        let result = sync_contents(&cargo_toml, package_json, None).unwrap();
+
+        let expected_package_json = r#"{
+  "name": "test-package",
+  "version": "1.1.0",
+  "publishConfig": {
+    "version": "1.0.0"
+  },
+  "volta": { "node": "22.0.0", "version": "1.0.0" }
+}
+"#;
+        assert_eq!(
+            expect_updated(&result, ManifestKind::PackageJson),
+            expected_package_json
+        );
+    }
+
+    #[test]
+    fn test_package_json_unusual_spacing() {
+        let cargo_toml = create_cargo_toml("1.1.0");
+
+        for (package_json, expected_package_json) in [
+            (
+                r#"{"name":"test-package","version":"1.0.0"}"#,
+                r#"{"name":"test-package","version":"1.1.0"}"#,
+            ),
+            (
+                "{\n\t\"version\" : \"1.0.0\"\n}\n",
+                "{\n\t\"version\" : \"1.1.0\"\n}\n",
+            ),
+        ] {
+            let result = sync_contents(&cargo_toml, package_json, None).unwrap();
+
+            assert_eq!(
+                expect_updated(&result, ManifestKind::PackageJson),
+                expected_package_json
+            );
+        }
+    }
+
+    const WORKSPACE_CARGO_TOML: &str = r#"[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "1.0.0" # shared by every member
+edition = "2024"
+"#;
+
+    const INHERITING_CARGO_TOML: &str = r#"[package]
+name = "test-package"
+version.workspace = true
+edition.workspace = true
+"#;
+
+    #[test]
+    fn test_inherited_version_already_equal() {
+        let package_json = create_package_json("1.0.0");
+
+        let result = sync_contents_with_workspace(
+            INHERITING_CARGO_TOML,
+            Some(WORKSPACE_CARGO_TOML),
+            &package_json,
+            None,
+        )
+        .unwrap();
+
+        assert!(result.is_in_sync());
+    }
+
+    #[test]
+    fn test_inherited_version_updates_workspace_root() {
+        let package_json = create_package_json("1.1.0");
+
+        let result = sync_contents_with_workspace(
+            INHERITING_CARGO_TOML,
+            Some(WORKSPACE_CARGO_TOML),
+            &package_json,
+            None,
+        )
+        .unwrap();
+
+        let expected_workspace_cargo_toml = r#"[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "1.1.0" # shared by every member
+edition = "2024"
+"#;
+        assert_eq!(
+            expect_updated(&result, ManifestKind::WorkspaceCargoToml),
+            expected_workspace_cargo_toml
+        );
+    }
+
+    #[test]
+    fn test_inherited_version_updates_package_json() {
+        let package_json = create_package_json("0.9.0");
+
+        let result = sync_contents_with_workspace(
+            INHERITING_CARGO_TOML,
+            Some(WORKSPACE_CARGO_TOML),
+            &package_json,
+            None,
+        )
+        .unwrap();
+
+        let expected_package_json = create_package_json("1.0.0");
+        assert_eq!(
+            expect_updated(&result, ManifestKind::PackageJson),
+            expected_package_json
+        );
+    }
+
+    #[test]
+    fn test_inherited_version_from_own_workspace() {
+        let cargo_toml = r#"[package]
+name = "test-package"
+version.workspace = true
+
+[workspace.package]
+version = "1.0.0"
+"#;
+        let package_json = create_package_json("2.0.0");
+
+        let result = sync_contents(cargo_toml, &package_json, None).unwrap();
+
+        let expected_cargo_toml = r#"[package]
+name = "test-package"
+version.workspace = true
+
+[workspace.package]
+version = "2.0.0"
+"#;
+        assert_eq!(
+            expect_updated(&result, ManifestKind::CargoToml),
+            expected_cargo_toml
+        );
+    }
+
+    #[test]
+    fn test_inherited_version_without_workspace() {
+        let package_json = create_package_json("1.0.0");
+
+        let result = sync_contents(INHERITING_CARGO_TOML, &package_json, None);
+
+        match result.unwrap_err().into_inner() {
+            Error::InheritedVersionUnresolved => {}
+            _ => panic!("Expected InheritedVersionUnresolved error"),
         }
     }
 }
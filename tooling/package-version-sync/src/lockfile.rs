@@ -0,0 +1,704 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::cargo::{inheriting_packages, replace_toml_string};
+use crate::json::{self, Node};
+use crate::manifest::{Manifest, ManifestKind, parse_version, verify_patch};
+use crate::package_json::json_type_name;
+use crate::report::{FileReport, SyncReport};
+use crate::{CargoToml, Error, PackageJson, SourceOfTruth, find_workspace_root};
+
+/// The entry of one local package in a Cargo.lock, whose version can be read and
+/// rewritten in place.
+///
+/// Cargo records the version of every workspace member in the lockfile, so
+/// rewriting `[package].version` leaves the lockfile stale, and
+/// `cargo build --locked` failing, until cargo runs again. Members inheriting
+/// `[workspace.package].version` all move together, so their entries are rewritten
+/// together.
+#[derive(Clone, Debug)]
+pub struct CargoLock {
+    path: PathBuf,
+    contents: String,
+    package: String,
+    inheriting: Vec<String>,
+}
+
+impl CargoLock {
+    /// Wraps Cargo.lock contents that are already in memory.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - Path the contents were read from
+    /// * `contents` - Raw Cargo.lock file contents
+    /// * `package` - Name of the local package whose entry is read and rewritten
+    pub fn from_contents(
+        path: impl Into<PathBuf>,
+        contents: impl Into<String>,
+        package: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            contents: contents.into(),
+            package: package.into(),
+            inheriting: Vec::new(),
+        }
+    }
+
+    /// Reads the Cargo.lock that records the crate of `cargo_toml`.
+    ///
+    /// The lockfile is looked for next to the workspace root manifest, or next to
+    /// the crate's own manifest when it is not part of a workspace or is a workspace
+    /// root itself. When the crate
+    /// inherits `[workspace.package].version`, the entries of every other member
+    /// inheriting it are rewritten along with the crate's own.
+    ///
+    /// # Returns
+    ///
+    /// `None` when the crate has no name, there is no lockfile, or the lockfile has
+    /// no entry for the crate.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Io` when the lockfile or a member manifest cannot be read and
+    /// `Error::Toml` when one is invalid, plus the errors of `find_workspace_root`.
+    pub fn find(cargo_toml: &CargoToml) -> Result<Option<Self>, Error> {
+        let Some(package) = cargo_toml.package_name() else {
+            return Ok(None);
+        };
+        // A manifest declaring `[workspace]` is its own root, and a parent workspace
+        // must not be searched for.
+        let workspace_root = if cargo_toml.declares_workspace() {
+            None
+        } else {
+            find_workspace_root(cargo_toml.path())?
+        };
+        let path = workspace_root
+            .as_deref()
+            .unwrap_or(cargo_toml.path())
+            .with_file_name("Cargo.lock");
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(Error::Io(error).in_file(&path, None)),
+        };
+
+        let mut cargo_lock = Self::from_contents(path, contents, package);
+        // With an inherited version, `cargo_toml.path()` is the workspace root.
+        if cargo_toml.inherits_version() {
+            cargo_lock.inheriting = inheriting_packages(cargo_toml.path())?
+                .into_iter()
+                .filter(|name| name != package)
+                .collect();
+        }
+        Ok(cargo_lock.has_version()?.then_some(cargo_lock))
+    }
+}
+
+impl Manifest for CargoLock {
+    fn kind(&self) -> ManifestKind {
+        ManifestKind::CargoLock
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    fn version(&self) -> Result<String, Error> {
+        read_lock_version(&self.contents, &self.package)
+            .map_err(|error| error.in_file(&self.path, Some(&self.contents)))
+    }
+
+    fn version_span(&self) -> Option<Range<usize>> {
+        let document = toml_edit::Document::parse(self.contents.as_str()).ok()?;
+        find_entry(document.as_table(), &self.package)?
+            .get("version")?
+            .span()
+    }
+
+    fn set_version(&mut self, version: &str) -> Result<(), Error> {
+        self.contents =
+            patch_lock_version(&self.contents, &self.package, &self.inheriting, version)
+                .and_then(|patched| {
+                    verify_patch("Cargo.lock", patched, version, |contents| {
+                        read_lock_version(contents, &self.package)
+                    })
+                })
+                .map_err(|error| error.in_file(&self.path, Some(&self.contents)))?;
+        Ok(())
+    }
+
+    fn has_version(&self) -> Result<bool, Error> {
+        let document = toml_edit::Document::parse(self.contents.as_str())
+            .map_err(|error| Error::TomlEdit(error).in_file(&self.path, Some(&self.contents)))?;
+        Ok(find_entry(document.as_table(), &self.package).is_some())
+    }
+
+    fn seed_version(&mut self, _version: &str) -> Result<(), Error> {
+        Err(Error::MissingLockEntry {
+            package: self.package.clone(),
+        }
+        .in_file(&self.path, Some(&self.contents)))
+    }
+}
+
+/// An npm package-lock.json, whose copies of the root package's version can be read
+/// and rewritten in place.
+///
+/// npm records the version both at the top level and under `packages[""]`, and
+/// `npm ci` rejects a lockfile where either disagrees with package.json.
+#[derive(Clone, Debug)]
+pub struct PackageLock {
+    path: PathBuf,
+    contents: String,
+}
+
+impl PackageLock {
+    /// Wraps package-lock.json contents that are already in memory.
+    pub fn from_contents(path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            contents: contents.into(),
+        }
+    }
+
+    /// Reads the package-lock.json next to `package_json`.
+    ///
+    /// # Returns
+    ///
+    /// `None` when there is no lockfile or it records no version, neither at the top
+    /// level nor under `packages[""]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Io` when the lockfile cannot be read and `Error::JsonSyntax`
+    /// when it is invalid.
+    pub fn find(package_json: &PackageJson) -> Result<Option<Self>, Error> {
+        let path = package_json.path().with_file_name("package-lock.json");
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(Error::Io(error).in_file(&path, None)),
+        };
+        let package_lock = Self::from_contents(path, contents);
+        Ok(package_lock.has_version()?.then_some(package_lock))
+    }
+
+    fn parse(&self) -> Result<Node, Error> {
+        json::parse(&self.contents)
+            .map_err(|error| Error::JsonSyntax(error).in_file(&self.path, Some(&self.contents)))
+    }
+
+    fn locate(&self, error: Error) -> Error {
+        match error {
+            Error::NonStringVersion { .. } => {
+                let span = self.version_span();
+                error.at(&self.path, Some(&self.contents), span)
+            }
+            error => error.in_file(&self.path, Some(&self.contents)),
+        }
+    }
+}
+
+impl Manifest for PackageLock {
+    fn kind(&self) -> ManifestKind {
+        ManifestKind::PackageLock
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    fn version(&self) -> Result<String, Error> {
+        read_package_lock_version(&self.contents).map_err(|error| self.locate(error))
+    }
+
+    fn version_span(&self) -> Option<Range<usize>> {
+        let root = json::parse(&self.contents).ok()?;
+        let [version, root_package_version] = version_nodes(&root);
+        Some(version.or(root_package_version)?.span.clone())
+    }
+
+    fn set_version(&mut self, version: &str) -> Result<(), Error> {
+        let root = self.parse()?;
+        let edits = version_nodes(&root)
+            .into_iter()
+            .flatten()
+            .filter(|node| node.as_str().is_some())
+            .map(|node| (node.span.clone(), json::quote(version)));
+        // Each copy is checked, so a missed one is reported rather than the other.
+        let read_versions = |contents: &str| {
+            let versions = read_package_lock_versions(contents)?;
+            match versions
+                .into_iter()
+                .flatten()
+                .find(|found| found != version)
+            {
+                Some(found) => Ok(found),
+                None => read_package_lock_version(contents),
+            }
+        };
+        self.contents = verify_patch(
+            "package-lock.json",
+            json::splice(&self.contents, edits),
+            version,
+            read_versions,
+        )
+        .map_err(|error| self.locate(error))?;
+        Ok(())
+    }
+
+    fn has_version(&self) -> Result<bool, Error> {
+        Ok(version_nodes(&self.parse()?).iter().any(Option::is_some))
+    }
+
+    fn seed_version(&mut self, _version: &str) -> Result<(), Error> {
+        Err(Error::MissingPackageLockVersion.in_file(&self.path, Some(&self.contents)))
+    }
+}
+
+/// Reads the lockfiles that record the versions of `cargo_toml` and
+/// `package_json`: Cargo.lock whenever there is one, and package-lock.json when
+/// `package_lock` is set.
+pub(crate) fn find_lockfiles(
+    cargo_toml: &CargoToml,
+    package_json: &PackageJson,
+    package_lock: bool,
+) -> Result<Vec<Box<dyn Manifest>>, Error> {
+    let mut lockfiles: Vec<Box<dyn Manifest>> = Vec::new();
+    if let Some(cargo_lock) = CargoLock::find(cargo_toml)? {
+        lockfiles.push(Box::new(cargo_lock));
+    }
+    if package_lock && let Some(package_lock) = PackageLock::find(package_json)? {
+        lockfiles.push(Box::new(package_lock));
+    }
+    Ok(lockfiles)
+}
+
+/// Brings each lockfile to the version `report` gave the manifest it locks, adding
+/// the lockfiles to `manifests` and to `report`, in the same order.
+pub(crate) fn sync_lockfiles(
+    lockfiles: Vec<Box<dyn Manifest>>,
+    manifests: &mut Vec<Box<dyn Manifest>>,
+    report: &mut SyncReport,
+) -> Result<(), Error> {
+    for mut lockfile in lockfiles {
+        let locked = match lockfile.kind() {
+            ManifestKind::CargoLock => SourceOfTruth::CargoToml,
+            _ => SourceOfTruth::PackageJson,
+        };
+        let Some(new_version) = report
+            .files
+            .iter()
+            .find(|file| file.kind.matches(&locked))
+            .map(|file| file.new_version.clone())
+        else {
+            continue;
+        };
+
+        let previous_version = parse_version(lockfile.as_ref(), &lockfile.version()?)?;
+        let updated = previous_version != new_version;
+        if updated {
+            lockfile.set_version(&new_version.to_string())?;
+        }
+        report.files.push(FileReport {
+            kind: lockfile.kind(),
+            path: lockfile.path().to_path_buf(),
+            previous_version: Some(previous_version),
+            new_version,
+            normalized_from: None,
+            contents: updated.then(|| lockfile.contents().to_string()),
+        });
+        manifests.push(lockfile);
+    }
+    Ok(())
+}
+
+/// The top-level `version` of a package-lock.json and the `version` of its root
+/// package under `packages[""]`, each when present.
+fn version_nodes(root: &Node) -> [Option<&Node>; 2] {
+    [
+        root.get("version"),
+        root.get("packages")
+            .and_then(|packages| packages.get(""))
+            .and_then(|package| package.get("version")),
+    ]
+}
+
+/// Reads the version a package-lock.json records: the top-level one, or else the
+/// one under `packages[""]`.
+fn read_package_lock_version(package_lock_contents: &str) -> Result<String, Error> {
+    let [version, root_package_version] = read_package_lock_versions(package_lock_contents)?;
+    version
+        .or(root_package_version)
+        .ok_or(Error::MissingPackageLockVersion)
+}
+
+/// Reads both copies of the version in a package-lock.json, in the order of
+/// `version_nodes`.
+fn read_package_lock_versions(package_lock_contents: &str) -> Result<[Option<String>; 2], Error> {
+    let parsed_package_lock =
+        serde_json::from_str::<serde_json::Value>(package_lock_contents).map_err(Error::Serde)?;
+    let read = |pointer| match parsed_package_lock.pointer(pointer) {
+        Some(serde_json::Value::String(version)) => Ok(Some(version.clone())),
+        Some(version) => Err(Error::NonStringVersion {
+            found_type: json_type_name(version),
+        }),
+        None => Ok(None),
+    };
+    Ok([read("/version")?, read("/packages//version")?])
+}
+
+/// Whether a `[[package]]` entry is the local package `package`; entries from a
+/// registry or git carry a `source` and are left alone.
+fn is_local_entry(entry: &toml_edit::Table, package: &str) -> bool {
+    entry.get("name").and_then(|name| name.as_str()) == Some(package)
+        && !entry.contains_key("source")
+}
+
+fn find_entry<'a>(cargo_lock: &'a toml_edit::Table, package: &str) -> Option<&'a toml_edit::Table> {
+    cargo_lock
+        .get("package")?
+        .as_array_of_tables()?
+        .iter()
+        .find(|entry| is_local_entry(entry, package))
+}
+
+fn read_lock_version(cargo_lock_contents: &str, package: &str) -> Result<String, Error> {
+    let document = toml_edit::Document::parse(cargo_lock_contents).map_err(Error::TomlEdit)?;
+    let version = find_entry(document.as_table(), package)
+        .and_then(|entry| entry.get("version"))
+        .ok_or_else(|| Error::MissingLockEntry {
+            package: package.to_string(),
+        })?;
+    version
+        .as_str()
+        .map(str::to_string)
+        .ok_or(Error::NonStringVersion {
+            found_type: version.type_name(),
+        })
+}
+
+/// Rewrites the `version` of `package`'s entry in place, along with those of the
+/// `inheriting` packages that have one, leaving every other entry untouched.
+fn patch_lock_version(
+    cargo_lock_contents: &str,
+    package: &str,
+    inheriting: &[String],
+    new_version: &str,
+) -> Result<String, Error> {
+    let mut document = cargo_lock_contents
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(Error::TomlEdit)?;
+    let entries = document
+        .get_mut("package")
+        .and_then(|entries| entries.as_array_of_tables_mut())
+        .ok_or_else(|| Error::MissingLockEntry {
+            package: package.to_string(),
+        })?;
+
+    let mut patched_package = false;
+    for entry in entries.iter_mut() {
+        let is_package = is_local_entry(entry, package);
+        if !is_package && !inheriting.iter().any(|name| is_local_entry(entry, name)) {
+            continue;
+        }
+        let Some(value) = entry
+            .get_mut("version")
+            .and_then(|version| version.as_value_mut())
+            .filter(|version| version.is_str())
+        else {
+            continue;
+        };
+        replace_toml_string(value, new_version);
+        patched_package |= is_package;
+    }
+    if !patched_package {
+        return Err(Error::MissingLockEntry {
+            package: package.to_string(),
+        });
+    }
+
+    Ok(document.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write;
+
+    const CARGO_LOCK: &str = r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 4
+
+[[package]]
+name = "my-app"
+version = "1.0.0"
+dependencies = [
+ "serde",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "0000"
+"#;
+
+    const PACKAGE_LOCK: &str = r#"{
+  "name": "my-app",
+  "version": "1.2.0",
+  "lockfileVersion": 3,
+  "requires": true,
+  "packages": {
+    "": {
+      "name": "my-app",
+      "version": "1.2.0",
+      "dependencies": {
+        "left-pad": "^1.3.0"
+      }
+    },
+    "node_modules/left-pad": {
+      "version": "1.3.0"
+    }
+  }
+}
+"#;
+
+    #[test]
+    fn test_set_version_only_touches_local_entry() {
+        let mut cargo_lock = CargoLock::from_contents("Cargo.lock", CARGO_LOCK, "my-app");
+
+        cargo_lock.set_version("1.1.0").unwrap();
+
+        assert_eq!(
+            cargo_lock.contents(),
+            CARGO_LOCK.replacen("version = \"1.0.0\"", "version = \"1.1.0\"", 1)
+        );
+        assert_eq!(cargo_lock.version().unwrap(), "1.1.0");
+    }
+
+    #[test]
+    fn test_sync_lockfiles_follow_their_manifests() {
+        let mut manifests: Vec<Box<dyn Manifest>> = vec![
+            Box::new(
+                CargoToml::from_contents(
+                    "Cargo.toml",
+                    "[package]\nname = \"my-app\"\nversion = \"1.0.0\"\n",
+                    None,
+                )
+                .unwrap(),
+            ),
+            Box::new(PackageJson::from_contents(
+                "package.json",
+                r#"{ "version": "1.2.0" }"#,
+            )),
+        ];
+        let mut report = crate::sync_manifests(&mut manifests, None).unwrap();
+        let lockfiles: Vec<Box<dyn Manifest>> = vec![
+            Box::new(CargoLock::from_contents("Cargo.lock", CARGO_LOCK, "my-app")),
+            Box::new(PackageLock::from_contents(
+                "package-lock.json",
+                PACKAGE_LOCK,
+            )),
+        ];
+
+        sync_lockfiles(lockfiles, &mut manifests, &mut report).unwrap();
+
+        let lockfile_reports = report.files[2..]
+            .iter()
+            .map(|file| (file.kind, file.previous_version.clone(), file.is_updated()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lockfile_reports,
+            vec![
+                (
+                    ManifestKind::CargoLock,
+                    Some(semver::Version::new(1, 0, 0)),
+                    true
+                ),
+                (
+                    ManifestKind::PackageLock,
+                    Some(semver::Version::new(1, 2, 0)),
+                    false
+                ),
+            ]
+        );
+        assert_eq!(manifests[2].version().unwrap(), "1.2.0");
+    }
+
+    #[test]
+    fn test_package_lock_patches_both_versions() {
+        let mut package_lock = PackageLock::from_contents("package-lock.json", PACKAGE_LOCK);
+
+        package_lock.set_version("1.3.0").unwrap();
+
+        assert_eq!(
+            package_lock.contents(),
+            PACKAGE_LOCK.replace("\"version\": \"1.2.0\"", "\"version\": \"1.3.0\"")
+        );
+    }
+
+    #[test]
+    fn test_package_lock_with_only_root_package_version() {
+        let contents = "{\n  \"lockfileVersion\": 3,\n  \"packages\": {\n    \"\": { \"version\": \"1.2.0\" }\n  }\n}\n";
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "package.json", "{ \"version\": \"1.3.0\" }");
+        write(dir.path(), "package-lock.json", contents);
+        let package_json = PackageJson::read(dir.path().join("package.json")).unwrap();
+
+        let mut package_lock = PackageLock::find(&package_json).unwrap().unwrap();
+        assert_eq!(package_lock.version().unwrap(), "1.2.0");
+        package_lock.set_version("1.3.0").unwrap();
+
+        assert_eq!(package_lock.contents(), contents.replace("1.2.0", "1.3.0"));
+        assert_eq!(
+            read_package_lock_versions(package_lock.contents()).unwrap(),
+            [None, Some("1.3.0".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_package_lock_verifies_root_package_version() {
+        let mut package_lock = PackageLock::from_contents(
+            "package-lock.json",
+            r#"{ "version": "1.2.0", "packages": { "": { "version": 12 } } }"#,
+        );
+
+        let error = package_lock.set_version("1.3.0").unwrap_err();
+
+        let Error::InFile { source, .. } = error else {
+            panic!("expected an error tied to the file, got {:?}", error);
+        };
+        assert!(matches!(
+            *source,
+            Error::NonStringVersion {
+                found_type: "number"
+            }
+        ));
+    }
+
+    #[test]
+    fn test_registry_entry_is_not_local() {
+        let cargo_lock = CargoLock::from_contents("Cargo.lock", CARGO_LOCK, "serde");
+
+        assert!(!cargo_lock.has_version().unwrap());
+    }
+
+    #[test]
+    fn test_find_lockfile_next_to_workspace_root() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        );
+        write(dir.path(), "Cargo.lock", CARGO_LOCK);
+        write(
+            dir.path(),
+            "crates/my-app/Cargo.toml",
+            "[package]\nname = \"my-app\"\nversion = \"1.0.0\"\n",
+        );
+        let cargo_toml_path = dir.path().join("crates/my-app/Cargo.toml");
+
+        let cargo_lock = CargoLock::find(&CargoToml::read(&cargo_toml_path).unwrap())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(cargo_lock.path(), dir.path().join("Cargo.lock"));
+        assert_eq!(cargo_lock.version().unwrap(), "1.0.0");
+    }
+
+    #[test]
+    fn test_find_lockfile_of_nested_workspace_root() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/my-app\"]\n",
+        );
+        write(dir.path(), "Cargo.lock", CARGO_LOCK);
+        write(
+            dir.path(),
+            "crates/my-app/Cargo.toml",
+            "[package]\nname = \"my-app\"\nversion = \"1.0.0\"\n\n[workspace]\n",
+        );
+        let cargo_toml_path = dir.path().join("crates/my-app/Cargo.toml");
+        let cargo_toml = CargoToml::read(&cargo_toml_path).unwrap();
+
+        assert!(CargoLock::find(&cargo_toml).unwrap().is_none());
+        write(dir.path(), "crates/my-app/Cargo.lock", CARGO_LOCK);
+        let cargo_lock = CargoLock::find(&cargo_toml).unwrap().unwrap();
+        assert_eq!(
+            cargo_lock.path(),
+            dir.path().join("crates/my-app/Cargo.lock")
+        );
+    }
+
+    #[test]
+    fn test_sync_updates_every_member_inheriting_the_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, contents: &str| write(dir.path(), path, contents);
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.package]\nversion = \"1.0.0\"\n",
+        );
+        for name in ["a", "b"] {
+            write(
+                &format!("crates/{}/Cargo.toml", name),
+                &format!("[package]\nname = \"{}\"\nversion.workspace = true\n", name),
+            );
+        }
+        write(
+            "crates/c/Cargo.toml",
+            "[package]\nname = \"c\"\nversion = \"1.0.0\"\n",
+        );
+        write("crates/a/package.json", r#"{ "version": "1.1.0" }"#);
+        let lock_entry =
+            |name: &str| format!("[[package]]\nname = \"{}\"\nversion = \"1.0.0\"\n", name);
+        write(
+            "Cargo.lock",
+            &format!(
+                "version = 4\n\n{}\n{}\n{}",
+                lock_entry("a"),
+                lock_entry("b"),
+                lock_entry("c")
+            ),
+        );
+
+        let report = crate::sync_files(
+            &dir.path().join("crates/a/Cargo.toml").to_string_lossy(),
+            &dir.path().join("crates/a/package.json").to_string_lossy(),
+            None,
+        )
+        .unwrap();
+
+        assert!(
+            report
+                .files
+                .iter()
+                .any(|file| file.kind == ManifestKind::CargoLock)
+        );
+        let cargo_lock = fs::read_to_string(dir.path().join("Cargo.lock")).unwrap();
+        for (name, version) in [("a", "1.1.0"), ("b", "1.1.0"), ("c", "1.0.0")] {
+            assert_eq!(
+                CargoLock::from_contents("Cargo.lock", cargo_lock.as_str(), name)
+                    .version()
+                    .unwrap(),
+                version,
+                "{}",
+                name
+            );
+        }
+    }
+}
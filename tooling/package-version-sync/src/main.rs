@@ -0,0 +1,771 @@
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use annotate_snippets::{AnnotationKind, Level, Renderer, Snippet};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use package_version_sync::{
+    AlignOptions, BuildMetadataPolicy, BumpKind, CargoLock, CargoToml, DependencyMismatch, Drift,
+    Error, Manifest, PackageJson, PackageLock, PendingChange, SourceOfTruth, SyncOptions,
+    SyncPolicy, SyncReport, Transaction, align_dependencies, bump_manifests, check_files,
+    discover_packages, dry_run_files, find_mismatches, resolve_workspace_ranges, sync_files,
+    sync_workspace,
+};
+
+/// Keeps the versions in a crate's Cargo.toml and package.json in step.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Path to the Cargo.toml file
+    #[arg(long, global = true, default_value = "Cargo.toml")]
+    cargo_toml: PathBuf,
+
+    /// Path to the package.json file
+    #[arg(long, global = true, default_value = "package.json")]
+    package_json: PathBuf,
+
+    /// Also update the version in the package-lock.json next to package.json
+    #[arg(long, global = true)]
+    update_package_lock: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Update whichever file is behind so both carry the same version
+    Sync {
+        #[command(flatten)]
+        policy: PolicyArgs,
+
+        /// Fail instead of picking a version when the files disagree
+        #[arg(long, conflicts_with = "source_of_truth")]
+        strict: bool,
+
+        /// Print a diff of the changes instead of writing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Print the sync report as JSON
+        #[arg(long, conflicts_with = "dry_run")]
+        json: bool,
+
+        /// Insert the synced version into a file that has no version, instead of
+        /// failing
+        #[arg(long)]
+        seed_missing_versions: bool,
+
+        /// Instead of one pair, every package of the workspace rooted at ROOT
+        /// (default: the current directory) found through pnpm-workspace.yaml and
+        /// the root Cargo.toml's `[workspace].members`
+        #[arg(
+            long,
+            value_name = "ROOT",
+            num_args = 0..=1,
+            default_missing_value = ".",
+            conflicts_with_all = ["dry_run", "json"]
+        )]
+        workspace: Option<PathBuf>,
+    },
+    /// Report version drift without writing, exiting non-zero when a sync would
+    /// change a file
+    Check {
+        #[command(flatten)]
+        policy: PolicyArgs,
+
+        /// Treat a file that has no version as one a sync would seed, instead of
+        /// failing
+        #[arg(long)]
+        seed_missing_versions: bool,
+
+        /// Instead of one pair, every package of the workspace rooted at ROOT
+        /// (default: the current directory) found through pnpm-workspace.yaml and
+        /// the root Cargo.toml's `[workspace].members`
+        #[arg(
+            long,
+            value_name = "ROOT",
+            num_args = 0..=1,
+            default_missing_value = "."
+        )]
+        workspace: Option<PathBuf>,
+    },
+    /// Advance the shared version in both files
+    Bump {
+        #[arg(value_enum)]
+        kind: BumpKindArg,
+
+        /// Pre-release identifier, such as `beta`, for the pre-release kinds
+        #[arg(long)]
+        preid: Option<String>,
+
+        /// Print a diff of the changes instead of writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the version found in each file
+    Show,
+    /// Replace the pnpm `workspace:` dependency ranges of a workspace with the
+    /// concrete ranges pnpm publishes
+    ResolveWorkspaceRanges {
+        /// Root of the pnpm workspace
+        #[arg(long, value_name = "ROOT", default_value = ".")]
+        workspace: PathBuf,
+
+        /// Write every package.json of the workspace under DIR instead of rewriting
+        /// them in place
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+    /// Work on the dependencies shared by the package.json files of a pnpm workspace
+    Deps {
+        #[command(subcommand)]
+        command: DepsCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum DepsCommand {
+    /// Report dependencies requested with different ranges across the workspace,
+    /// exiting non-zero when any are, and optionally rewrite them to one range
+    Align {
+        /// Root of the pnpm workspace
+        #[arg(long, value_name = "ROOT", default_value = ".")]
+        workspace: PathBuf,
+
+        /// Rewrite every usage of NAME to RANGE, such as `--set react=19.2.0`
+        #[arg(long, value_name = "NAME=RANGE", value_parser = parse_range_choice)]
+        set: Vec<(String, String)>,
+
+        /// Rewrite each other mismatched dependency to its pnpm override, or else to
+        /// its range with the highest version
+        #[arg(long)]
+        highest: bool,
+    },
+}
+
+fn parse_range_choice(choice: &str) -> Result<(String, String), String> {
+    match choice.split_once('=') {
+        Some((name, range)) if !name.is_empty() && !range.is_empty() => {
+            Ok((name.to_string(), range.to_string()))
+        }
+        _ => Err(format!("expected NAME=RANGE, found {}", choice)),
+    }
+}
+
+/// Options shared by the commands that decide which version wins.
+#[derive(Args)]
+struct PolicyArgs {
+    /// Always take the version from this file instead of the newer one
+    #[arg(long, value_enum)]
+    source_of_truth: Option<SourceOfTruthArg>,
+
+    /// Let --source-of-truth move a file to a lower version
+    #[arg(long)]
+    allow_downgrade: bool,
+
+    /// How build metadata (`+...`) is compared and carried between the files
+    #[arg(long, value_enum, default_value = "propagate")]
+    build_metadata: BuildMetadataArg,
+
+    /// Accept versions such as `v1.2.3`, `=1.2.3` or ` 1.2.3 `, as npm's
+    /// `semver.clean` does
+    #[arg(long)]
+    lenient_versions: bool,
+
+    /// Rewrite versions accepted by --lenient-versions to their canonical form
+    #[arg(long, requires = "lenient_versions")]
+    canonicalize_versions: bool,
+}
+
+impl PolicyArgs {
+    fn options(
+        &self,
+        strict: bool,
+        seed_missing_versions: bool,
+        update_package_lock: bool,
+    ) -> SyncOptions {
+        SyncOptions {
+            policy: if strict {
+                SyncPolicy::Strict
+            } else {
+                self.source_of_truth.map(SourceOfTruth::from).into()
+            },
+            seed_missing_versions,
+            allow_downgrade: self.allow_downgrade,
+            build_metadata: self.build_metadata.into(),
+            lenient_versions: self.lenient_versions,
+            canonicalize_versions: self.canonicalize_versions,
+            update_package_lock,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SourceOfTruthArg {
+    Cargo,
+    PackageJson,
+}
+
+impl From<SourceOfTruthArg> for SourceOfTruth {
+    fn from(arg: SourceOfTruthArg) -> Self {
+        match arg {
+            SourceOfTruthArg::Cargo => SourceOfTruth::CargoToml,
+            SourceOfTruthArg::PackageJson => SourceOfTruth::PackageJson,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum BuildMetadataArg {
+    /// Compare it like the rest of the version and copy it everywhere
+    Propagate,
+    /// Treat versions that differ only in build metadata as equal
+    Ignore,
+    /// Like ignore, and keep it out of Cargo.toml
+    StripCargo,
+    /// Like ignore, and keep it out of package.json
+    StripPackageJson,
+}
+
+impl From<BuildMetadataArg> for BuildMetadataPolicy {
+    fn from(arg: BuildMetadataArg) -> Self {
+        match arg {
+            BuildMetadataArg::Propagate => BuildMetadataPolicy::Propagate,
+            BuildMetadataArg::Ignore => BuildMetadataPolicy::Ignore,
+            BuildMetadataArg::StripCargo => BuildMetadataPolicy::Strip(SourceOfTruth::CargoToml),
+            BuildMetadataArg::StripPackageJson => {
+                BuildMetadataPolicy::Strip(SourceOfTruth::PackageJson)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum BumpKindArg {
+    Major,
+    Minor,
+    Patch,
+    Premajor,
+    Preminor,
+    Prepatch,
+    Prerelease,
+    Release,
+}
+
+impl BumpKindArg {
+    fn with_preid(self, preid: Option<String>) -> BumpKind {
+        match self {
+            BumpKindArg::Major => BumpKind::Major,
+            BumpKindArg::Minor => BumpKind::Minor,
+            BumpKindArg::Patch => BumpKind::Patch,
+            BumpKindArg::Premajor => BumpKind::Premajor(preid),
+            BumpKindArg::Preminor => BumpKind::Preminor(preid),
+            BumpKindArg::Prepatch => BumpKind::Prepatch(preid),
+            BumpKindArg::Prerelease => BumpKind::Prerelease(preid),
+            BumpKindArg::Release => BumpKind::Release,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(&cli) {
+        Ok(exit_code) => exit_code,
+        Err(error) => {
+            report_error(&error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<ExitCode, Error> {
+    match &cli.command {
+        Command::Sync {
+            policy,
+            strict,
+            seed_missing_versions,
+            workspace: Some(root),
+            ..
+        } => {
+            let options = policy.options(*strict, *seed_missing_versions, cli.update_package_lock);
+            let mut exit_code = ExitCode::SUCCESS;
+            let workspace = sync_workspace(root, options)?;
+            for sync in workspace.packages {
+                let prefix = format!("{}: ", sync.package.dir.display());
+                match sync.result {
+                    Ok(report) => print_report(&prefix, &report, root),
+                    Err(error) => {
+                        report_error(&error);
+                        exit_code = ExitCode::FAILURE;
+                    }
+                }
+            }
+            for change in &workspace.requirements {
+                println!(
+                    "{}: {} requirement {} -> {}",
+                    relative_to(&change.path, root).display(),
+                    change.dependency,
+                    change.previous_requirement,
+                    change.new_requirement
+                );
+            }
+            Ok(exit_code)
+        }
+        Command::Sync {
+            policy,
+            strict,
+            dry_run: true,
+            seed_missing_versions,
+            ..
+        } => {
+            let changes = dry_run_files(
+                &cli.cargo_toml.to_string_lossy(),
+                &cli.package_json.to_string_lossy(),
+                policy.options(*strict, *seed_missing_versions, cli.update_package_lock),
+            )?;
+            print_diffs(&changes);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Sync {
+            policy,
+            strict,
+            dry_run: false,
+            json,
+            seed_missing_versions,
+            workspace: None,
+        } => {
+            let report = sync_files(
+                &cli.cargo_toml.to_string_lossy(),
+                &cli.package_json.to_string_lossy(),
+                policy.options(*strict, *seed_missing_versions, cli.update_package_lock),
+            )?;
+            if *json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report).map_err(Error::Serde)?
+                );
+            } else {
+                print_report("", &report, Path::new(""));
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Check {
+            policy,
+            seed_missing_versions,
+            workspace: Some(root),
+        } => {
+            let mut exit_code = ExitCode::SUCCESS;
+            for package in discover_packages(root)? {
+                let drift = check_files(
+                    &package.cargo_toml.to_string_lossy(),
+                    &package.package_json.to_string_lossy(),
+                    policy.options(false, *seed_missing_versions, cli.update_package_lock),
+                );
+                let prefix = format!("{}: ", package.dir.display());
+                if !matches!(drift, Ok(ref drift) if drift.is_in_sync()) {
+                    exit_code = ExitCode::FAILURE;
+                }
+                match drift {
+                    Ok(drift) => print_drift(&prefix, &drift),
+                    Err(error) => report_error(&error),
+                }
+            }
+            Ok(exit_code)
+        }
+        Command::Check {
+            policy,
+            seed_missing_versions,
+            workspace: None,
+        } => {
+            let drift = check_files(
+                &cli.cargo_toml.to_string_lossy(),
+                &cli.package_json.to_string_lossy(),
+                policy.options(false, *seed_missing_versions, cli.update_package_lock),
+            )?;
+            print_drift("", &drift);
+            Ok(if drift.is_in_sync() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+        Command::Bump {
+            kind,
+            preid,
+            dry_run,
+        } => {
+            let mut manifests = read_manifests(cli)?;
+            let originals = manifests
+                .iter()
+                .map(|manifest| manifest.contents().to_string())
+                .collect::<Vec<_>>();
+            let changes = bump_manifests(&mut manifests, kind.with_preid(preid.clone()))?;
+            if *dry_run {
+                let pending = changes
+                    .into_iter()
+                    .map(|change| {
+                        PendingChange::new(
+                            change.path,
+                            originals[change.index].clone(),
+                            manifests[change.index].contents().to_string(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                print_diffs(&pending);
+                return Ok(ExitCode::SUCCESS);
+            }
+            let mut transaction = Transaction::new();
+            for change in &changes {
+                transaction.stage_manifest(manifests[change.index].as_ref());
+            }
+            transaction.commit()?;
+            for change in changes {
+                println!(
+                    "{}: {} -> {}",
+                    change.path.display(),
+                    change.previous_version,
+                    change.new_version
+                );
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Show => {
+            for manifest in read_manifests(cli)? {
+                println!("{}: {}", manifest.path().display(), manifest.version()?);
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Deps {
+            command:
+                DepsCommand::Align {
+                    workspace,
+                    set,
+                    highest,
+                },
+        } => {
+            if !set.is_empty() || *highest {
+                let options = AlignOptions {
+                    ranges: set.iter().cloned().collect(),
+                    highest: *highest,
+                };
+                for change in align_dependencies(workspace, options)? {
+                    println!(
+                        "{}: {} {} -> {}",
+                        change.path.display(),
+                        change.dependency,
+                        change.previous_range,
+                        change.new_range
+                    );
+                }
+            }
+            let mismatches = find_mismatches(workspace)?;
+            for mismatch in &mismatches {
+                print_mismatch(mismatch);
+            }
+            Ok(if mismatches.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+        Command::ResolveWorkspaceRanges { workspace, out_dir } => {
+            for manifest in resolve_workspace_ranges(workspace, out_dir.as_deref())? {
+                for range in &manifest.ranges {
+                    println!(
+                        "{}: {} {} -> {}",
+                        manifest.output.display(),
+                        range.dependency,
+                        range.previous_range,
+                        range.new_range
+                    );
+                }
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+fn print_mismatch(mismatch: &DependencyMismatch) {
+    println!("{} is requested with different ranges:", mismatch.name);
+    for usage in &mismatch.usages {
+        println!(
+            "  {} ({}): {}",
+            usage.path.display(),
+            usage.field,
+            usage.range
+        );
+    }
+    if let Some(range) = &mismatch.override_range {
+        println!("  pnpm-workspace.yaml (overrides): {}", range);
+    }
+}
+
+/// Reads both files, plus the crate's Cargo.lock entry when there is one and the
+/// package-lock.json when asked to.
+fn read_manifests(cli: &Cli) -> Result<Vec<Box<dyn Manifest>>, Error> {
+    let cargo_toml = CargoToml::read(&cli.cargo_toml)?;
+    let cargo_lock = CargoLock::find(&cargo_toml)?;
+    let package_json = PackageJson::read(&cli.package_json)?;
+    let package_lock = if cli.update_package_lock {
+        PackageLock::find(&package_json)?
+    } else {
+        None
+    };
+    let mut manifests: Vec<Box<dyn Manifest>> = vec![Box::new(cargo_toml), Box::new(package_json)];
+    if let Some(cargo_lock) = cargo_lock {
+        manifests.push(Box::new(cargo_lock));
+    }
+    if let Some(package_lock) = package_lock {
+        manifests.push(Box::new(package_lock));
+    }
+    Ok(manifests)
+}
+
+/// Prints the files a sync rewrote, or that there was nothing to do, with their
+/// paths relative to `root`.
+fn print_report(prefix: &str, report: &SyncReport, root: &Path) {
+    for line in report_lines(prefix, report, root) {
+        println!("{}", line);
+    }
+}
+
+fn report_lines(prefix: &str, report: &SyncReport, root: &Path) -> Vec<String> {
+    let mut lines = Vec::new();
+    if report.is_in_sync() {
+        lines.push(format!("{}versions in sync at {}", prefix, report.version));
+    }
+    for file in &report.files {
+        let path = relative_to(&file.path, root);
+        let path = path.display();
+        match (&file.previous_version, &file.normalized_from) {
+            (None, _) => lines.push(format!("{}: seeded {}", path, file.new_version)),
+            (Some(_), Some(written)) if file.is_updated() => {
+                lines.push(format!("{}: {:?} -> {}", path, written, file.new_version))
+            }
+            (Some(previous_version), Some(written)) => lines.push(format!(
+                "{}: read {:?} as {}",
+                path, written, previous_version
+            )),
+            (Some(previous_version), None) if file.is_updated() => lines.push(format!(
+                "{}: {} -> {}",
+                path, previous_version, file.new_version
+            )),
+            (Some(_), None) => {}
+        }
+    }
+    lines
+}
+
+/// `path` relative to `root`, or as it is when it lies outside of `root`. The two
+/// are compared canonicalized when they do not share a spelling, such as a
+/// relative root and an absolute path.
+fn relative_to(path: &Path, root: &Path) -> PathBuf {
+    if let Ok(relative) = path.strip_prefix(root) {
+        return relative.to_path_buf();
+    }
+    match (path.canonicalize(), root.canonicalize()) {
+        (Ok(path), Ok(root)) if path.starts_with(&root) => {
+            path.strip_prefix(&root).unwrap_or(&path).to_path_buf()
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Prints an error to stderr, with the offending source lines when it points into a
+/// file.
+fn report_error(error: &Error) {
+    let Error::InFile {
+        path,
+        contents: Some(contents),
+        span: Some(span),
+        source,
+    } = error
+    else {
+        eprintln!("error: {}", describe(error));
+        return;
+    };
+
+    let title = describe(source.as_ref());
+    let path = path.to_string_lossy();
+    let report = [Level::ERROR.primary_title(title).element(
+        Snippet::source(contents.as_str())
+            .path(path.as_ref())
+            .annotation(AnnotationKind::Primary.span(span.clone())),
+    )];
+    let renderer = if std::io::stderr().is_terminal() {
+        Renderer::styled()
+    } else {
+        Renderer::plain()
+    };
+    eprintln!("{}", renderer.render(&report));
+}
+
+/// Joins an error's message with the messages of its sources.
+fn describe(error: &(dyn std::error::Error + 'static)) -> String {
+    let mut messages = Vec::new();
+    let mut next = Some(error);
+    while let Some(error) = next {
+        // TOML errors render their own snippet; only their message is wanted here.
+        messages.push(
+            if let Some(error) = error.downcast_ref::<toml::de::Error>() {
+                error.message().to_string()
+            } else if let Some(error) = error.downcast_ref::<toml_edit::TomlError>() {
+                error.message().to_string()
+            } else {
+                error.to_string()
+            },
+        );
+        next = error.source();
+    }
+    messages.join(": ")
+}
+
+/// Prints a check's outcome, drift going to stderr.
+fn print_drift(prefix: &str, drift: &Drift) {
+    let shown = |version: &Option<String>| version.as_deref().unwrap_or("no version").to_string();
+    if drift.is_in_sync() {
+        println!(
+            "{}versions in sync at {}",
+            prefix,
+            shown(&drift.cargo_version)
+        );
+    } else {
+        let kinds = drift
+            .would_update
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        eprintln!(
+            "{}version drift: Cargo.toml has {}, package.json has {}; sync would update {}",
+            prefix,
+            shown(&drift.cargo_version),
+            shown(&drift.package_json_version),
+            kinds.join(" and ")
+        );
+    }
+}
+
+/// Prints each pending change's diff, colored when stdout is a terminal.
+fn print_diffs(changes: &[PendingChange]) {
+    let color = std::io::stdout().is_terminal();
+    for change in changes {
+        for line in change.diff.lines() {
+            let style = match line {
+                _ if !color => None,
+                _ if line.starts_with("---") || line.starts_with("+++") => Some("1"),
+                _ if line.starts_with("@@") => Some("36"),
+                _ if line.starts_with('-') => Some("31"),
+                _ if line.starts_with('+') => Some("32"),
+                _ => None,
+            };
+            match style {
+                Some(style) => println!("\x1b[{}m{}\x1b[0m", style, line),
+                None => println!("{}", line),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use package_version_sync::sync_workspace;
+
+    use super::*;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_workspace_report_paths_are_relative_to_root() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"packages/*\"]\n\n[workspace.package]\nversion = \"1.1.0\"\n",
+        );
+        write(
+            root.path(),
+            "Cargo.lock",
+            "[[package]]\nname = \"a\"\nversion = \"1.0.0\"\n",
+        );
+        write(
+            root.path(),
+            "packages/a/Cargo.toml",
+            "[package]\nname = \"a\"\nversion.workspace = true\n",
+        );
+        write(
+            root.path(),
+            "packages/a/package.json",
+            "{ \"version\": \"1.0.0\" }",
+        );
+
+        let workspace = sync_workspace(root.path(), None).unwrap();
+
+        let report = workspace.packages[0].result.as_ref().unwrap();
+        assert_eq!(
+            report_lines("packages/a: ", report, root.path()),
+            [
+                "packages/a/package.json: 1.0.0 -> 1.1.0",
+                "Cargo.lock: 1.0.0 -> 1.1.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relative_to_compares_canonical_paths() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "packages/a/Cargo.toml", "");
+
+        assert_eq!(
+            relative_to(
+                &root.path().join("packages/a/Cargo.toml"),
+                &root.path().join("packages/..")
+            ),
+            Path::new("packages/a/Cargo.toml")
+        );
+        assert_eq!(
+            relative_to(Path::new("/elsewhere/Cargo.toml"), root.path()),
+            Path::new("/elsewhere/Cargo.toml")
+        );
+    }
+
+    #[test]
+    fn test_check_fails_on_stale_package_lock_when_asked_to() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            "Cargo.toml",
+            "[package]\nname = \"a\"\nversion = \"1.1.0\"\n",
+        );
+        write(root.path(), "package.json", "{ \"version\": \"1.1.0\" }");
+        write(
+            root.path(),
+            "package-lock.json",
+            "{ \"version\": \"1.0.0\" }",
+        );
+        let cargo_toml = root.path().join("Cargo.toml");
+        let package_json = root.path().join("package.json");
+        let check = |flags: &[&str]| {
+            let mut args = vec![
+                "package-version-sync",
+                "--cargo-toml",
+                cargo_toml.to_str().unwrap(),
+                "--package-json",
+                package_json.to_str().unwrap(),
+            ];
+            args.extend(flags);
+            args.push("check");
+            run(&Cli::try_parse_from(args).unwrap()).unwrap()
+        };
+
+        assert_eq!(check(&[]), ExitCode::SUCCESS);
+        assert_eq!(check(&["--update-package-lock"]), ExitCode::FAILURE);
+    }
+}
@@ -0,0 +1,857 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::report::{FileReport, SyncReason, SyncReport};
+use crate::write::write_atomic;
+use crate::{Error, SourceOfTruth};
+
+/// The kind of version-bearing file a `Manifest` wraps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ManifestKind {
+    /// A crate's Cargo.toml holding `[package].version`
+    CargoToml,
+    /// A workspace root Cargo.toml holding the `[workspace.package].version` that a
+    /// crate inherits
+    WorkspaceCargoToml,
+    /// A package.json
+    PackageJson,
+    /// A Cargo.lock, holding the version of a workspace member's entry
+    CargoLock,
+    /// An npm package-lock.json, holding the root package's version
+    PackageLock,
+}
+
+impl ManifestKind {
+    /// Whether a manifest of this kind speaks for `source_of_truth`.
+    pub fn matches(self, source_of_truth: &SourceOfTruth) -> bool {
+        match source_of_truth {
+            SourceOfTruth::CargoToml => {
+                matches!(
+                    self,
+                    ManifestKind::CargoToml | ManifestKind::WorkspaceCargoToml
+                )
+            }
+            SourceOfTruth::PackageJson => self == ManifestKind::PackageJson,
+        }
+    }
+}
+
+impl From<SourceOfTruth> for ManifestKind {
+    fn from(source_of_truth: SourceOfTruth) -> Self {
+        match source_of_truth {
+            SourceOfTruth::CargoToml => ManifestKind::CargoToml,
+            SourceOfTruth::PackageJson => ManifestKind::PackageJson,
+        }
+    }
+}
+
+impl fmt::Display for ManifestKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestKind::CargoToml => write!(f, "Cargo.toml"),
+            ManifestKind::WorkspaceCargoToml => write!(f, "workspace Cargo.toml"),
+            ManifestKind::PackageJson => write!(f, "package.json"),
+            ManifestKind::CargoLock => write!(f, "Cargo.lock"),
+            ManifestKind::PackageLock => write!(f, "package-lock.json"),
+        }
+    }
+}
+
+/// A file that carries a package version.
+///
+/// Implementations hold the file contents in memory: `set_version` only rewrites
+/// those contents, and nothing touches the disk until `save` is called.
+pub trait Manifest {
+    /// The kind of file this is
+    fn kind(&self) -> ManifestKind;
+
+    /// Path of the file that holds the version
+    fn path(&self) -> &Path;
+
+    /// Current contents of the file, including any pending version change
+    fn contents(&self) -> &str;
+
+    /// Reads the version string from the current contents
+    fn version(&self) -> Result<String, Error>;
+
+    /// Byte range of the version value in the current contents, used to point
+    /// errors at it
+    fn version_span(&self) -> Option<Range<usize>> {
+        None
+    }
+
+    /// Rewrites the version in the current contents
+    fn set_version(&mut self, version: &str) -> Result<(), Error>;
+
+    /// Whether the current contents declare a version at all, valid or not
+    fn has_version(&self) -> Result<bool, Error>;
+
+    /// Inserts a version into contents that declare none
+    fn seed_version(&mut self, version: &str) -> Result<(), Error>;
+
+    /// Writes the current contents back to `path`, atomically and keeping the
+    /// file's permissions
+    fn save(&self) -> Result<(), Error> {
+        write_atomic(self.path(), self.contents())
+    }
+}
+
+/// How `sync_manifests` picks the version every manifest is brought to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SyncPolicy {
+    /// Use the highest version among all manifests
+    #[default]
+    Newest,
+    /// Use the version of the first manifest of the given kind
+    SourceOfTruth(SourceOfTruth),
+    /// Refuse to pick a version: fail with `Error::Conflict` unless every manifest
+    /// that has a version already carries the same one
+    Strict,
+}
+
+impl From<Option<SourceOfTruth>> for SyncPolicy {
+    fn from(source_of_truth: Option<SourceOfTruth>) -> Self {
+        match source_of_truth {
+            Some(source_of_truth) => SyncPolicy::SourceOfTruth(source_of_truth),
+            None => SyncPolicy::Newest,
+        }
+    }
+}
+
+/// How `sync_manifests` treats the build metadata (`+...`) of versions.
+///
+/// SemVer gives build metadata no precedence, but `semver::Version` orders it
+/// lexically, so by default `1.0.0+build.1` counts as newer than `1.0.0`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum BuildMetadataPolicy {
+    /// Compare build metadata like any other part of the version and copy it to
+    /// every manifest
+    #[default]
+    Propagate,
+    /// Compare versions by precedence only, leaving manifests whose versions differ
+    /// just in build metadata untouched
+    Ignore,
+    /// Compare like `Ignore`, and keep build metadata out of the given file's
+    /// version; `Strip(SourceOfTruth::PackageJson)` keeps a `+sha` in Cargo.toml only
+    Strip(SourceOfTruth),
+}
+
+impl BuildMetadataPolicy {
+    fn compare(&self, a: &semver::Version, b: &semver::Version) -> Ordering {
+        match self {
+            BuildMetadataPolicy::Propagate => a.cmp(b),
+            BuildMetadataPolicy::Ignore | BuildMetadataPolicy::Strip(_) => a.cmp_precedence(b),
+        }
+    }
+
+    fn strips(&self, kind: ManifestKind) -> bool {
+        matches!(self, BuildMetadataPolicy::Strip(side) if kind.matches(side))
+    }
+}
+
+/// Settings for `sync_manifests` and the sync functions built on it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SyncOptions {
+    /// How the target version is chosen
+    pub policy: SyncPolicy,
+    /// Insert the target version into manifests that declare none, instead of
+    /// failing with `Error::MissingCargoVersion` or `Error::MissingPackageJsonVersion`
+    pub seed_missing_versions: bool,
+    /// Let a forced source of truth move a manifest to a lower version, instead of
+    /// failing with `Error::Downgrade`
+    pub allow_downgrade: bool,
+    /// How build metadata is compared and carried over
+    pub build_metadata: BuildMetadataPolicy,
+    /// Accept versions that npm's `semver.clean` would, such as `v1.2.3`, `=1.2.3`
+    /// or ` 1.2.3 `, by reading them through `clean_version`
+    pub lenient_versions: bool,
+    /// Rewrite versions that `lenient_versions` had to clean to their canonical
+    /// form, even when they are otherwise in sync
+    pub canonicalize_versions: bool,
+    /// Also bring the package-lock.json next to package.json to the synced version,
+    /// in the file-based sync functions
+    pub update_package_lock: bool,
+}
+
+impl From<SyncPolicy> for SyncOptions {
+    fn from(policy: SyncPolicy) -> Self {
+        Self {
+            policy,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<Option<SourceOfTruth>> for SyncOptions {
+    fn from(source_of_truth: Option<SourceOfTruth>) -> Self {
+        SyncPolicy::from(source_of_truth).into()
+    }
+}
+
+/// A version change made to one manifest by `bump_manifests`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManifestChange {
+    /// Index of the manifest in the slice passed to `bump_manifests`
+    pub index: usize,
+    /// Kind of the manifest that changed
+    pub kind: ManifestKind,
+    /// Path of the file that changed
+    pub path: PathBuf,
+    /// Version before the change
+    pub previous_version: String,
+    /// Version after the change
+    pub new_version: String,
+}
+
+/// Syncs version numbers across any number of manifests.
+///
+/// Picks a target version according to the options' policy and rewrites, in
+/// memory, every manifest whose version differs from it. Versions are compared as
+/// semantic versions, so manifests that already agree are left alone. With
+/// `seed_missing_versions`, manifests without a version are given the target version
+/// and are never picked as the source.
+///
+/// # Parameters
+///
+/// * `manifests` - The manifests to reconcile
+/// * `options` - How the target version is chosen; a `SyncPolicy` or
+///   `Option<SourceOfTruth>` converts into options with defaults for the rest
+///
+/// # Returns
+///
+/// A `SyncReport` with one entry per manifest, in slice order. Call
+/// `Manifest::save` on the manifests it reports as updated to write the changes to
+/// disk.
+///
+/// # Errors
+///
+/// * `Error::MissingCargoVersion`, `Error::MissingPackageJsonVersion` - A manifest
+///   has no version and seeding is off
+/// * `Error::NonStringVersion` - A manifest's version is not a string
+/// * `Error::NoManifests` - No manifests were given
+/// * `Error::NoVersionToSync` - No manifest has a version to seed from
+/// * `Error::SourceOfTruthNotFound`, `Error::SourceOfTruthUnversioned` - No manifest
+///   matches the policy's source of truth, or it has no version to seed from
+/// * `Error::Conflict` - The policy is `SyncPolicy::Strict` and the versions differ
+/// * `Error::Downgrade` - A manifest would move to a lower version and downgrades
+///   are not allowed
+/// * `Error::Semver` - Invalid semantic version strings
+/// * `Error::PatchFailed` - A rewritten manifest did not carry the new version
+///
+/// # Examples
+///
+/// ```rust
+/// use package_version_sync::{sync_manifests, CargoToml, Manifest, PackageJson, SyncPolicy};
+///
+/// let mut manifests: Vec<Box<dyn Manifest>> = vec![
+///     Box::new(CargoToml::from_contents(
+///         "Cargo.toml",
+///         "[package]\nname = \"my-app\"\nversion = \"1.0.0\"\n",
+///         None,
+///     )?),
+///     Box::new(PackageJson::from_contents(
+///         "package.json",
+///         r#"{ "name": "my-app", "version": "1.1.0" }"#,
+///     )),
+///     Box::new(PackageJson::from_contents(
+///         "npm/package.json",
+///         r#"{ "name": "@my-app/cli", "version": "1.0.0" }"#,
+///     )),
+/// ];
+///
+/// let report = sync_manifests(&mut manifests, SyncPolicy::Newest)?;
+/// assert_eq!(report.updated_files().count(), 2);
+/// assert_eq!(manifests[0].version()?, "1.1.0");
+/// # Ok::<(), package_version_sync::Error>(())
+/// ```
+pub fn sync_manifests(
+    manifests: &mut [Box<dyn Manifest>],
+    options: impl Into<SyncOptions>,
+) -> Result<SyncReport, Error> {
+    let SyncOptions {
+        policy,
+        seed_missing_versions,
+        allow_downgrade,
+        build_metadata,
+        lenient_versions,
+        canonicalize_versions,
+        ..
+    } = options.into();
+    if manifests.is_empty() {
+        return Err(Error::NoManifests);
+    }
+
+    let versions = manifests
+        .iter()
+        .map(|manifest| {
+            if seed_missing_versions && !manifest.has_version()? {
+                return Ok(None);
+            }
+            let version = manifest.version()?;
+            let cleaned = if lenient_versions {
+                clean_version(&version)
+            } else {
+                &version
+            };
+            let version_semver = parse_version(manifest.as_ref(), cleaned)?;
+            Ok(Some((version, version_semver)))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let target = match &policy {
+        SyncPolicy::Newest => versions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, version)| Some((index, &version.as_ref()?.1)))
+            .reduce(|newest, candidate| {
+                if build_metadata.compare(candidate.1, newest.1).is_gt() {
+                    candidate
+                } else {
+                    newest
+                }
+            })
+            .map(|(index, _)| index)
+            .ok_or(Error::NoVersionToSync)?,
+        SyncPolicy::SourceOfTruth(source_of_truth) => manifests
+            .iter()
+            .position(|manifest| manifest.kind().matches(source_of_truth))
+            .ok_or_else(|| Error::SourceOfTruthNotFound {
+                kind: ManifestKind::from(source_of_truth.clone()),
+            })?,
+        SyncPolicy::Strict => versions
+            .iter()
+            .position(Option::is_some)
+            .ok_or(Error::NoVersionToSync)?,
+    };
+    let (target_version, target_version_semver) =
+        versions[target]
+            .clone()
+            .ok_or_else(|| Error::SourceOfTruthUnversioned {
+                kind: manifests[target].kind(),
+            })?;
+
+    if policy == SyncPolicy::Strict
+        && versions.iter().flatten().any(|(_, version_semver)| {
+            build_metadata
+                .compare(version_semver, &target_version_semver)
+                .is_ne()
+        })
+    {
+        return Err(Error::Conflict {
+            versions: manifests
+                .iter()
+                .zip(&versions)
+                .filter_map(|(manifest, version)| {
+                    let (version, _) = version.as_ref()?;
+                    Some((manifest.path().to_path_buf(), version.clone()))
+                })
+                .collect(),
+        });
+    }
+
+    if !allow_downgrade
+        && let Some((manifest, (version, _))) = manifests
+            .iter()
+            .zip(&versions)
+            .filter_map(|(manifest, version)| Some((manifest, version.as_ref()?)))
+            .find(|(_, (_, version_semver))| {
+                build_metadata
+                    .compare(version_semver, &target_version_semver)
+                    .is_gt()
+            })
+    {
+        return Err(Error::Downgrade {
+            path: manifest.path().to_path_buf(),
+            from: version.clone(),
+            to: target_version,
+        });
+    }
+
+    let mut files = Vec::new();
+    for (manifest, version) in manifests.iter_mut().zip(versions) {
+        let strip = build_metadata.strips(manifest.kind());
+        let (normalized_from, previous_version) = match version {
+            Some((version, version_semver)) => (
+                (lenient_versions && clean_version(&version) != version).then_some(version),
+                Some(version_semver),
+            ),
+            None => (None, None),
+        };
+        let new_version = match &previous_version {
+            Some(version_semver)
+                if build_metadata
+                    .compare(version_semver, &target_version_semver)
+                    .is_eq()
+                    && (!strip || version_semver.build.is_empty()) =>
+            {
+                version_semver.clone()
+            }
+            _ if strip => semver::Version {
+                build: semver::BuildMetadata::EMPTY,
+                ..target_version_semver.clone()
+            },
+            _ => target_version_semver.clone(),
+        };
+        let updated = previous_version.as_ref() != Some(&new_version)
+            || (canonicalize_versions && normalized_from.is_some());
+        match &previous_version {
+            None => manifest.seed_version(&new_version.to_string())?,
+            Some(_) if updated => manifest.set_version(&new_version.to_string())?,
+            Some(_) => {}
+        }
+        files.push(FileReport {
+            kind: manifest.kind(),
+            path: manifest.path().to_path_buf(),
+            previous_version,
+            new_version,
+            normalized_from,
+            contents: updated.then(|| manifest.contents().to_string()),
+        });
+    }
+
+    let source = manifests[target].kind();
+    let reason = if files
+        .iter()
+        .all(|file| file.previous_version.as_ref() == Some(&file.new_version))
+    {
+        SyncReason::AlreadyInSync
+    } else {
+        match policy {
+            SyncPolicy::Newest => SyncReason::Newest { source },
+            SyncPolicy::SourceOfTruth(_) => SyncReason::SourceOfTruth { source },
+            SyncPolicy::Strict => SyncReason::Agreed { source },
+        }
+    };
+
+    Ok(SyncReport {
+        reason,
+        version: target_version_semver,
+        files,
+    })
+}
+
+/// Parses a manifest's version, pointing a failure at the version in the file.
+pub(crate) fn parse_version(
+    manifest: &dyn Manifest,
+    version: &str,
+) -> Result<semver::Version, Error> {
+    semver::Version::parse(version).map_err(|error| {
+        Error::Semver(error).at(
+            manifest.path(),
+            Some(manifest.contents()),
+            manifest.version_span(),
+        )
+    })
+}
+
+/// Strips what npm's `semver.clean` strips from a version before parsing it:
+/// surrounding whitespace and any leading `=` or `v`.
+///
+/// # Examples
+///
+/// ```rust
+/// use package_version_sync::clean_version;
+///
+/// assert_eq!(clean_version(" =v1.2.3 "), "1.2.3");
+/// assert_eq!(clean_version("1.2.3-beta.1"), "1.2.3-beta.1");
+/// ```
+pub fn clean_version(version: &str) -> &str {
+    version
+        .trim()
+        .trim_start_matches(|c: char| c == '=' || c == 'v' || c.is_whitespace())
+}
+
+/// Re-reads a patched file and checks that it now carries `expected`, so a rewrite
+/// that silently missed is reported instead of being written out as an update.
+pub(crate) fn verify_patch(
+    file: &'static str,
+    patched_contents: String,
+    expected: &str,
+    read_version: impl Fn(&str) -> Result<String, Error>,
+) -> Result<String, Error> {
+    let found = read_version(&patched_contents)?;
+    if found != expected {
+        return Err(Error::PatchFailed {
+            file,
+            expected: expected.to_string(),
+            found,
+        });
+    }
+    Ok(patched_contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{create_cargo_toml, create_package_json};
+    use crate::{CargoToml, PackageJson};
+
+    fn cargo_toml_manifest(version: &str) -> Box<dyn Manifest> {
+        Box::new(CargoToml::from_contents("Cargo.toml", create_cargo_toml(version), None).unwrap())
+    }
+
+    fn package_json_manifest(path: &str, version: &str) -> Box<dyn Manifest> {
+        Box::new(PackageJson::from_contents(
+            path,
+            create_package_json(version),
+        ))
+    }
+
+    #[test]
+    fn test_newest_version_wins_across_all_manifests() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.0.0"),
+            package_json_manifest("package.json", "1.2.0"),
+            package_json_manifest("npm/package.json", "1.1.0"),
+        ];
+
+        let report = sync_manifests(&mut manifests, SyncPolicy::Newest).unwrap();
+
+        assert_eq!(
+            report.reason,
+            SyncReason::Newest {
+                source: ManifestKind::PackageJson
+            }
+        );
+        assert_eq!(report.version, semver::Version::new(1, 2, 0));
+        let updated = report
+            .updated_files()
+            .map(|(index, file)| {
+                let previous_version = file.previous_version.as_ref().unwrap().to_string();
+                (index, file.path.clone(), previous_version)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            updated,
+            vec![
+                (0, PathBuf::from("Cargo.toml"), "1.0.0".to_string()),
+                (2, PathBuf::from("npm/package.json"), "1.1.0".to_string()),
+            ]
+        );
+        for manifest in &manifests {
+            assert_eq!(manifest.version().unwrap(), "1.2.0");
+        }
+    }
+
+    #[test]
+    fn test_source_of_truth_uses_first_manifest_of_kind() {
+        let mut manifests = vec![
+            package_json_manifest("package.json", "2.0.0"),
+            cargo_toml_manifest("1.0.0"),
+            package_json_manifest("npm/package.json", "3.0.0"),
+        ];
+        let options = SyncOptions {
+            policy: SyncPolicy::SourceOfTruth(SourceOfTruth::CargoToml),
+            allow_downgrade: true,
+            ..SyncOptions::default()
+        };
+
+        let report = sync_manifests(&mut manifests, options).unwrap();
+
+        assert_eq!(
+            report.reason,
+            SyncReason::SourceOfTruth {
+                source: ManifestKind::CargoToml
+            }
+        );
+        assert_eq!(report.updated_files().count(), 2);
+        for manifest in &manifests {
+            assert_eq!(manifest.version().unwrap(), "1.0.0");
+        }
+    }
+
+    #[test]
+    fn test_strict_conflict() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.0.0"),
+            package_json_manifest("package.json", "2.0.0"),
+        ];
+
+        let result = sync_manifests(&mut manifests, SyncPolicy::Strict);
+
+        match result.unwrap_err() {
+            Error::Conflict { versions } => assert_eq!(
+                versions,
+                vec![
+                    (PathBuf::from("Cargo.toml"), "1.0.0".to_string()),
+                    (PathBuf::from("package.json"), "2.0.0".to_string()),
+                ]
+            ),
+            _ => panic!("Expected Conflict error"),
+        }
+        assert_eq!(manifests[0].version().unwrap(), "1.0.0");
+    }
+
+    #[test]
+    fn test_strict_seeds_agreed_version() {
+        let mut manifests = vec![
+            Box::new(PackageJson::from_contents("package.json", "{}")) as Box<dyn Manifest>,
+            cargo_toml_manifest("1.0.0"),
+            package_json_manifest("npm/package.json", "1.0.0"),
+        ];
+        let options = SyncOptions {
+            policy: SyncPolicy::Strict,
+            seed_missing_versions: true,
+            ..SyncOptions::default()
+        };
+
+        let report = sync_manifests(&mut manifests, options).unwrap();
+
+        assert_eq!(
+            report.reason,
+            SyncReason::Agreed {
+                source: ManifestKind::CargoToml
+            }
+        );
+        assert_eq!(report.updated_files().count(), 1);
+        assert_eq!(manifests[0].version().unwrap(), "1.0.0");
+    }
+
+    #[test]
+    fn test_source_of_truth_rejects_downgrade() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.0.0"),
+            package_json_manifest("package.json", "1.1.0"),
+            package_json_manifest("npm/package.json", "2.0.0"),
+        ];
+
+        let result = sync_manifests(
+            &mut manifests,
+            SyncPolicy::SourceOfTruth(SourceOfTruth::CargoToml),
+        );
+
+        match result.unwrap_err() {
+            Error::Downgrade { path, from, to } => {
+                assert_eq!(path, PathBuf::from("package.json"));
+                assert_eq!(from, "1.1.0");
+                assert_eq!(to, "1.0.0");
+            }
+            _ => panic!("Expected Downgrade error"),
+        }
+        assert_eq!(manifests[2].version().unwrap(), "2.0.0");
+    }
+
+    #[test]
+    fn test_strip_build_metadata_keeps_it_on_the_other_side() {
+        let mut manifests = vec![
+            package_json_manifest("package.json", "1.0.0+abc123"),
+            cargo_toml_manifest("1.0.0+abc123"),
+            package_json_manifest("npm/package.json", "1.0.0"),
+        ];
+        let options = SyncOptions {
+            build_metadata: BuildMetadataPolicy::Strip(SourceOfTruth::PackageJson),
+            ..SyncOptions::default()
+        };
+
+        let report = sync_manifests(&mut manifests, options).unwrap();
+
+        let updated = report
+            .updated_files()
+            .map(|(index, file)| (index, file.new_version.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(updated, vec![(0, "1.0.0".to_string())]);
+        assert_eq!(manifests[1].version().unwrap(), "1.0.0+abc123");
+    }
+
+    #[test]
+    fn test_ignored_build_metadata_does_not_pick_newest() {
+        let mut manifests = vec![
+            package_json_manifest("package.json", "1.0.0"),
+            cargo_toml_manifest("1.0.0+build.2"),
+        ];
+        let options = SyncOptions {
+            policy: SyncPolicy::Strict,
+            build_metadata: BuildMetadataPolicy::Ignore,
+            ..SyncOptions::default()
+        };
+
+        let report = sync_manifests(&mut manifests, options).unwrap();
+
+        assert_eq!(report.reason, SyncReason::AlreadyInSync);
+    }
+
+    #[test]
+    fn test_lenient_versions_are_normalized() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.2.3"),
+            package_json_manifest("package.json", "v1.2.3"),
+            package_json_manifest("npm/package.json", "=1.2.2"),
+        ];
+        let options = SyncOptions {
+            lenient_versions: true,
+            ..SyncOptions::default()
+        };
+
+        let report = sync_manifests(&mut manifests, options).unwrap();
+
+        let normalized = report
+            .files
+            .iter()
+            .map(|file| file.normalized_from.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(normalized, vec![None, Some("v1.2.3"), Some("=1.2.2")]);
+        assert_eq!(report.updated_files().count(), 1);
+        assert_eq!(manifests[1].version().unwrap(), "v1.2.3");
+        assert_eq!(manifests[2].version().unwrap(), "1.2.3");
+    }
+
+    #[test]
+    fn test_canonicalize_lenient_versions() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.2.3"),
+            package_json_manifest("package.json", " v1.2.3 "),
+        ];
+        let options = SyncOptions {
+            lenient_versions: true,
+            canonicalize_versions: true,
+            ..SyncOptions::default()
+        };
+
+        let report = sync_manifests(&mut manifests, options).unwrap();
+
+        assert_eq!(report.reason, SyncReason::AlreadyInSync);
+        assert_eq!(report.updated_files().count(), 1);
+        assert_eq!(manifests[1].version().unwrap(), "1.2.3");
+    }
+
+    #[test]
+    fn test_lenient_versions_off_by_default() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.2.3"),
+            package_json_manifest("package.json", "v1.2.3"),
+        ];
+
+        let result = sync_manifests(&mut manifests, SyncPolicy::Newest);
+
+        assert!(matches!(result.unwrap_err().into_inner(), Error::Semver(_)));
+    }
+
+    #[test]
+    fn test_missing_source_of_truth() {
+        let mut manifests = vec![package_json_manifest("package.json", "1.0.0")];
+
+        let result = sync_manifests(
+            &mut manifests,
+            SyncPolicy::SourceOfTruth(SourceOfTruth::CargoToml),
+        );
+
+        assert!(matches!(
+            result.unwrap_err(),
+            Error::SourceOfTruthNotFound {
+                kind: ManifestKind::CargoToml
+            }
+        ));
+    }
+
+    #[test]
+    fn test_already_in_sync() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.0.0"),
+            package_json_manifest("package.json", "1.0.0"),
+        ];
+
+        let report = sync_manifests(&mut manifests, SyncPolicy::Newest).unwrap();
+
+        assert_eq!(report.reason, SyncReason::AlreadyInSync);
+        assert!(report.is_in_sync());
+    }
+
+    #[test]
+    fn test_seed_missing_versions() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.2.0"),
+            Box::new(PackageJson::from_contents(
+                "package.json",
+                "{\n  \"name\": \"test-package\"\n}\n",
+            )) as Box<dyn Manifest>,
+        ];
+        let options = SyncOptions {
+            seed_missing_versions: true,
+            ..SyncOptions::default()
+        };
+
+        let report = sync_manifests(&mut manifests, options).unwrap();
+
+        assert_eq!(report.files[1].previous_version, None);
+        assert_eq!(
+            report.updated_contents(ManifestKind::PackageJson),
+            Some("{\n  \"name\": \"test-package\",\n  \"version\": \"1.2.0\"\n}\n")
+        );
+    }
+
+    #[test]
+    fn test_missing_version_without_seeding() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.2.0"),
+            Box::new(PackageJson::from_contents("package.json", "{}")) as Box<dyn Manifest>,
+        ];
+
+        let result = sync_manifests(&mut manifests, SyncPolicy::Newest);
+
+        match result.unwrap_err().into_inner() {
+            Error::MissingPackageJsonVersion => {}
+            _ => panic!("Expected MissingPackageJsonVersion error"),
+        }
+    }
+
+    #[test]
+    fn test_seeding_source_of_truth_without_version() {
+        let mut manifests = vec![
+            cargo_toml_manifest("1.2.0"),
+            Box::new(PackageJson::from_contents("package.json", "{}")) as Box<dyn Manifest>,
+        ];
+        let options = SyncOptions {
+            policy: SyncPolicy::SourceOfTruth(SourceOfTruth::PackageJson),
+            seed_missing_versions: true,
+            ..SyncOptions::default()
+        };
+
+        let result = sync_manifests(&mut manifests, options);
+
+        assert!(matches!(
+            result.unwrap_err(),
+            Error::SourceOfTruthUnversioned {
+                kind: ManifestKind::PackageJson
+            }
+        ));
+    }
+
+    #[test]
+    fn test_no_manifests() {
+        let result = sync_manifests(&mut [], SyncPolicy::Newest);
+
+        assert!(matches!(result.unwrap_err(), Error::NoManifests));
+    }
+
+    #[test]
+    fn test_verify_patch_rejects_missed_rewrite() {
+        let unpatched = r#"{ "version": "1.0.0" }"#.to_string();
+
+        let result = verify_patch("package.json", unpatched, "1.1.0", |contents| {
+            Ok(
+                serde_json::from_str::<serde_json::Value>(contents).unwrap()["version"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+            )
+        });
+
+        match result.unwrap_err() {
+            Error::PatchFailed {
+                file,
+                expected,
+                found,
+            } => {
+                assert_eq!(file, "package.json");
+                assert_eq!(expected, "1.1.0");
+                assert_eq!(found, "1.0.0");
+            }
+            _ => panic!("Expected PatchFailed error"),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::json::NodeKind;
+use crate::manifest::{Manifest, ManifestKind, verify_patch};
+use crate::{Error, json};
+
+/// A package.json whose top-level `version` can be read and rewritten in place.
+#[derive(Clone, Debug)]
+pub struct PackageJson {
+    path: PathBuf,
+    contents: String,
+}
+
+impl PackageJson {
+    /// Wraps package.json contents that are already in memory.
+    pub fn from_contents(path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            contents: contents.into(),
+        }
+    }
+
+    /// Reads a package.json from disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Io`, tied to `path`, when the file cannot be read.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let contents =
+            fs::read_to_string(path).map_err(|error| Error::Io(error).in_file(path, None))?;
+        Ok(Self::from_contents(path, contents))
+    }
+
+    fn locate(&self, error: Error) -> Error {
+        match error {
+            Error::NonStringVersion { .. } => {
+                let span = self.version_span();
+                error.at(&self.path, Some(&self.contents), span)
+            }
+            error => error.in_file(&self.path, Some(&self.contents)),
+        }
+    }
+}
+
+impl Manifest for PackageJson {
+    fn kind(&self) -> ManifestKind {
+        ManifestKind::PackageJson
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    fn version(&self) -> Result<String, Error> {
+        read_package_json_version(&self.contents).map_err(|error| self.locate(error))
+    }
+
+    fn version_span(&self) -> Option<Range<usize>> {
+        let root = json::parse(&self.contents).ok()?;
+        Some(root.get("version")?.span.clone())
+    }
+
+    fn set_version(&mut self, version: &str) -> Result<(), Error> {
+        self.contents =
+            update_package_json(&self.contents, version).map_err(|error| self.locate(error))?;
+        Ok(())
+    }
+
+    fn has_version(&self) -> Result<bool, Error> {
+        let root =
+            json::parse(&self.contents).map_err(|error| self.locate(Error::JsonSyntax(error)))?;
+        Ok(root.get("version").is_some())
+    }
+
+    fn seed_version(&mut self, version: &str) -> Result<(), Error> {
+        self.contents = insert_package_json_version(&self.contents, version)
+            .and_then(|patched| {
+                verify_patch("package.json", patched, version, read_package_json_version)
+            })
+            .map_err(|error| self.locate(error))?;
+        Ok(())
+    }
+}
+
+fn read_package_json_version(package_json_contents: &str) -> Result<String, Error> {
+    let parsed_package_json =
+        serde_json::from_str::<serde_json::Value>(package_json_contents).map_err(Error::Serde)?;
+    match parsed_package_json.get("version") {
+        Some(serde_json::Value::String(version)) => Ok(version.clone()),
+        Some(version) => Err(Error::NonStringVersion {
+            found_type: json_type_name(version),
+        }),
+        None => Err(Error::MissingPackageJsonVersion),
+    }
+}
+
+pub(crate) fn json_type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+fn update_package_json(package_json_contents: &str, new_version: &str) -> Result<String, Error> {
+    verify_patch(
+        "package.json",
+        patch_package_json_version(package_json_contents, new_version)?,
+        new_version,
+        read_package_json_version,
+    )
+}
+
+/// Rewrites the value of the top-level `version` member by byte span, so nested
+/// `version` keys and the surrounding formatting are left as they were.
+fn patch_package_json_version(
+    package_json_contents: &str,
+    new_version: &str,
+) -> Result<String, Error> {
+    let root = json::parse(package_json_contents).map_err(Error::JsonSyntax)?;
+    let span = root
+        .get("version")
+        .filter(|version| version.as_str().is_some())
+        .map(|version| version.span.clone())
+        .ok_or(Error::MissingPackageJsonVersion)?;
+
+    let mut patched = package_json_contents.to_string();
+    patched.replace_range(span, &json::quote(new_version));
+    Ok(patched)
+}
+
+/// Inserts a top-level `version` member right after `name`, or first when there is
+/// no `name`, copying the layout of the neighbouring member so the new line matches
+/// the file's indentation and key/value spacing.
+fn insert_package_json_version(
+    package_json_contents: &str,
+    new_version: &str,
+) -> Result<String, Error> {
+    let root = json::parse(package_json_contents).map_err(Error::JsonSyntax)?;
+    let NodeKind::Object(members) = &root.kind else {
+        return Err(Error::NotAnObject);
+    };
+
+    let Some(neighbour) = root.member("name").or(members.first()) else {
+        let mut patched = package_json_contents.to_string();
+        patched.insert_str(
+            root.span.start + 1,
+            &format!("\"version\": {}", json::quote(new_version)),
+        );
+        return Ok(patched);
+    };
+
+    let before_key = &package_json_contents[..neighbour.key_span.start];
+    let indent = &before_key[before_key.trim_end().len()..];
+    let separator = match indent.rfind('\n') {
+        Some(newline) if indent[..newline].ends_with('\r') => &indent[newline - 1..],
+        Some(newline) => &indent[newline..],
+        None => indent,
+    };
+    let colon = &package_json_contents[neighbour.key_span.end..neighbour.value.span.start];
+    let member = format!("\"version\"{}{}", colon, json::quote(new_version));
+
+    let mut patched = package_json_contents.to_string();
+    if neighbour.key == "name" {
+        patched.insert_str(
+            neighbour.value.span.end,
+            &format!(",{}{}", separator, member),
+        );
+    } else {
+        patched.insert_str(
+            neighbour.key_span.start,
+            &format!("{},{}", member, separator),
+        );
+    }
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_version_carries_new_version() {
+        let mut package_json = PackageJson::from_contents(
+            "package.json",
+            "{\n  \"name\": \"test-package\",\n  \"version\": \"1.0.0\"\n}\n",
+        );
+
+        package_json.set_version("1.2.0").unwrap();
+
+        assert_eq!(package_json.version().unwrap(), "1.2.0");
+        assert_eq!(
+            package_json.contents(),
+            "{\n  \"name\": \"test-package\",\n  \"version\": \"1.2.0\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_seed_version_after_name() {
+        let mut package_json = PackageJson::from_contents(
+            "package.json",
+            "{\n    \"name\": \"test-package\",\n    \"scripts\": {}\n}\n",
+        );
+
+        assert!(!package_json.has_version().unwrap());
+        package_json.seed_version("1.2.0").unwrap();
+
+        assert_eq!(
+            package_json.contents(),
+            "{\n    \"name\": \"test-package\",\n    \"version\": \"1.2.0\",\n    \"scripts\": {}\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_seed_version_layouts() {
+        let seed = |contents: &str| {
+            let mut package_json = PackageJson::from_contents("package.json", contents);
+            package_json.seed_version("1.2.0").unwrap();
+            package_json.contents().to_string()
+        };
+
+        assert_eq!(
+            seed("{\"name\":\"test-package\"}"),
+            "{\"name\":\"test-package\",\"version\":\"1.2.0\"}"
+        );
+        assert_eq!(
+            seed("{ \"private\": true }"),
+            "{ \"version\": \"1.2.0\", \"private\": true }"
+        );
+        assert_eq!(
+            seed("{\n\t\"private\": true\n}"),
+            "{\n\t\"version\": \"1.2.0\",\n\t\"private\": true\n}"
+        );
+        assert_eq!(seed("{}"), "{\"version\": \"1.2.0\"}");
+    }
+
+    #[test]
+    fn test_non_string_version() {
+        let package_json = PackageJson::from_contents("package.json", r#"{ "version": 1 }"#);
+
+        match package_json.version().unwrap_err().into_inner() {
+            Error::NonStringVersion { found_type } => assert_eq!(found_type, "number"),
+            error => panic!("Expected NonStringVersion error, got {:?}", error),
+        }
+    }
+}
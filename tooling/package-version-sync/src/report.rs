@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::manifest::ManifestKind;
+
+/// Outcome of a sync: the version every file ended up at, why it was chosen, and
+/// what happened to each file.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SyncReport {
+    /// Why `version` was chosen
+    pub reason: SyncReason,
+    /// Version every file carries after the sync, up to the build metadata a
+    /// `BuildMetadataPolicy` other than `Propagate` leaves alone or strips
+    pub version: semver::Version,
+    /// One entry per file, in the order the files were given
+    pub files: Vec<FileReport>,
+}
+
+/// Why a sync chose the version it did.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum SyncReason {
+    /// Every file already carried the same version, though some may have been
+    /// rewritten to its canonical form
+    AlreadyInSync,
+    /// `source` had the highest version
+    Newest { source: ManifestKind },
+    /// `source` was forced as the source of truth
+    SourceOfTruth { source: ManifestKind },
+    /// Every file with a version already agreed, as `SyncPolicy::Strict` requires;
+    /// only files without one were seeded, from `source`
+    Agreed { source: ManifestKind },
+}
+
+/// What a sync did to one file.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FileReport {
+    /// Kind of the file
+    pub kind: ManifestKind,
+    /// Path of the file
+    pub path: PathBuf,
+    /// Version before the sync, or `None` when the file had none and was seeded
+    pub previous_version: Option<semver::Version>,
+    /// Version after the sync
+    pub new_version: semver::Version,
+    /// Version as written in the file, when lenient parsing had to clean it to
+    /// read `previous_version`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized_from: Option<String>,
+    /// Patched contents, present only when the file was rewritten
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contents: Option<String>,
+}
+
+impl SyncReport {
+    /// Whether no file needed rewriting.
+    pub fn is_in_sync(&self) -> bool {
+        self.updated_files().next().is_none()
+    }
+
+    /// The files that were rewritten, with their index in `files`.
+    pub fn updated_files(&self) -> impl Iterator<Item = (usize, &FileReport)> {
+        self.files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| file.is_updated())
+    }
+
+    /// Patched contents of the first rewritten file of the given kind.
+    pub fn updated_contents(&self, kind: ManifestKind) -> Option<&str> {
+        self.updated_files()
+            .find(|(_, file)| file.kind == kind)
+            .and_then(|(_, file)| file.contents.as_deref())
+    }
+}
+
+impl FileReport {
+    /// Whether the sync rewrote this file.
+    pub fn is_updated(&self) -> bool {
+        self.contents.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_serializes_to_json() {
+        let report = SyncReport {
+            reason: SyncReason::Newest {
+                source: ManifestKind::PackageJson,
+            },
+            version: semver::Version::new(1, 1, 0),
+            files: vec![
+                FileReport {
+                    kind: ManifestKind::CargoToml,
+                    path: PathBuf::from("Cargo.toml"),
+                    previous_version: Some(semver::Version::new(1, 0, 0)),
+                    new_version: semver::Version::new(1, 1, 0),
+                    normalized_from: None,
+                    contents: Some("[package]\nversion = \"1.1.0\"\n".to_string()),
+                },
+                FileReport {
+                    kind: ManifestKind::PackageJson,
+                    path: PathBuf::from("package.json"),
+                    previous_version: Some(semver::Version::new(1, 1, 0)),
+                    new_version: semver::Version::new(1, 1, 0),
+                    normalized_from: None,
+                    contents: None,
+                },
+            ],
+        };
+
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({
+                "reason": { "kind": "newest", "source": "package-json" },
+                "version": "1.1.0",
+                "files": [
+                    {
+                        "kind": "cargo-toml",
+                        "path": "Cargo.toml",
+                        "previous_version": "1.0.0",
+                        "new_version": "1.1.0",
+                        "contents": "[package]\nversion = \"1.1.0\"\n"
+                    },
+                    {
+                        "kind": "package-json",
+                        "path": "package.json",
+                        "previous_version": "1.1.0",
+                        "new_version": "1.1.0"
+                    }
+                ]
+            })
+        );
+        assert_eq!(report.updated_files().count(), 1);
+        assert!(!report.is_in_sync());
+    }
+}
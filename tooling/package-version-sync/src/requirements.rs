@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml_edit::TableLike;
+
+use crate::cargo::replace_toml_string;
+use crate::{CargoToml, Error, Manifest, Transaction};
+
+/// Dependency tables that can hold a path dependency's version requirement.
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// A path dependency's version requirement, rewritten to admit the version of the
+/// workspace crate it points at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequirementChange {
+    /// Cargo.toml that declares the dependency
+    pub path: PathBuf,
+    /// Key of the dependency, as written in that manifest
+    pub dependency: String,
+    /// Requirement before the change
+    pub previous_requirement: String,
+    /// Requirement after the change
+    pub new_requirement: String,
+    /// Version of the crate the dependency points at
+    pub version: semver::Version,
+}
+
+/// Rewrites every path dependency requirement among the Cargo manifests of the
+/// workspace at `root` that does not admit the current version of the crate it
+/// points at.
+///
+/// `members` are the member directories, relative to `root`. The member manifests
+/// and the root manifest, including its `[workspace.dependencies]`, are scanned.
+/// Requirements made of a single bare, `^`, `~` or `=` comparator are rewritten
+/// keeping their operator and the number of version parts they spell out; other
+/// forms are left as they are. Every rewrite is written in one `Transaction`.
+pub(crate) fn sync_requirements(
+    root: &Path,
+    members: &[PathBuf],
+) -> Result<Vec<RequirementChange>, Error> {
+    let mut manifest_paths = members
+        .iter()
+        .map(|dir| root.join(dir).join("Cargo.toml"))
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+
+    let mut versions = HashMap::new();
+    for path in &manifest_paths {
+        let cargo_toml = CargoToml::read(path)?;
+        if cargo_toml.has_version()? {
+            let version = cargo_toml.version()?;
+            let version = crate::manifest::parse_version(&cargo_toml, &version)?;
+            versions.insert(crate_dir(path)?, version);
+        }
+    }
+
+    let root_manifest = root.join("Cargo.toml");
+    if root_manifest.is_file() {
+        let root_dir = crate_dir(&root_manifest)?;
+        let mut member_dirs = manifest_paths.iter().map(|path| crate_dir(path));
+        if !member_dirs.any(|dir| dir.is_ok_and(|dir| dir == root_dir)) {
+            manifest_paths.insert(0, root_manifest);
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut transaction = Transaction::new();
+    for path in &manifest_paths {
+        let contents =
+            fs::read_to_string(path).map_err(|error| Error::Io(error).in_file(path, None))?;
+        let mut document = contents
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(|error| Error::TomlEdit(error).in_file(path, Some(&contents)))?;
+        let dir = crate_dir(path)?;
+
+        let mut changed = false;
+        for table in dependency_tables(&mut document) {
+            for (dependency, item) in table.iter_mut() {
+                let Some(dependency_table) = item.as_table_like_mut() else {
+                    continue;
+                };
+                let Some(dependency_path) = dependency_table
+                    .get("path")
+                    .and_then(|path| path.as_str())
+                    .map(|path| dir.join(path))
+                else {
+                    continue;
+                };
+                let Some(version) = fs::canonicalize(&dependency_path)
+                    .ok()
+                    .and_then(|dependency_dir| versions.get(&dependency_dir))
+                else {
+                    continue;
+                };
+                let Some(value) = dependency_table
+                    .get_mut("version")
+                    .and_then(|requirement| requirement.as_value_mut())
+                else {
+                    continue;
+                };
+                let Some(previous_requirement) = value.as_str().map(str::to_string) else {
+                    continue;
+                };
+                let Some(new_requirement) = updated_requirement(&previous_requirement, version)
+                else {
+                    continue;
+                };
+
+                replace_toml_string(value, &new_requirement);
+                changed = true;
+                changes.push(RequirementChange {
+                    path: path.clone(),
+                    dependency: dependency.get().to_string(),
+                    previous_requirement,
+                    new_requirement,
+                    version: version.clone(),
+                });
+            }
+        }
+        if changed {
+            transaction.stage(path, document.to_string());
+        }
+    }
+    transaction.commit()?;
+
+    Ok(changes)
+}
+
+fn crate_dir(cargo_toml_path: &Path) -> Result<PathBuf, Error> {
+    let dir = cargo_toml_path.parent().unwrap_or(Path::new("."));
+    fs::canonicalize(dir).map_err(|error| Error::Io(error).in_file(cargo_toml_path, None))
+}
+
+/// The dependency tables of a manifest: the top-level ones, those under each
+/// `[target.*]`, and `[workspace.dependencies]`.
+fn dependency_tables(document: &mut toml_edit::DocumentMut) -> Vec<&mut dyn TableLike> {
+    let mut tables = Vec::new();
+    for (key, item) in document.as_table_mut().iter_mut() {
+        let Some(table) = item.as_table_like_mut() else {
+            continue;
+        };
+        match key.get() {
+            key if DEPENDENCY_TABLES.contains(&key) => tables.push(table),
+            "target" => {
+                for (_, target) in table.iter_mut() {
+                    let Some(target) = target.as_table_like_mut() else {
+                        continue;
+                    };
+                    for (key, item) in target.iter_mut() {
+                        if DEPENDENCY_TABLES.contains(&key.get()) {
+                            tables.extend(item.as_table_like_mut());
+                        }
+                    }
+                }
+            }
+            "workspace" => {
+                tables.extend(
+                    table
+                        .get_mut("dependencies")
+                        .and_then(|dependencies| dependencies.as_table_like_mut()),
+                );
+            }
+            _ => {}
+        }
+    }
+    tables
+}
+
+/// Rewrites `requirement` to admit `version`, keeping its operator and how many
+/// version parts it spells out.
+///
+/// Returns `None` when the requirement already admits `version`, or is not a single
+/// bare, `^`, `~` or `=` comparator.
+fn updated_requirement(requirement: &str, version: &semver::Version) -> Option<String> {
+    let parsed = semver::VersionReq::parse(requirement).ok()?;
+    if parsed.matches(version) {
+        return None;
+    }
+    let [comparator] = parsed.comparators.as_slice() else {
+        return None;
+    };
+    let operator = match requirement.trim_start().chars().next()? {
+        '^' => "^",
+        '~' => "~",
+        '=' => "=",
+        c if c.is_ascii_digit() => "",
+        _ => return None,
+    };
+
+    let new_requirement = if !version.pre.is_empty() || comparator.patch.is_some() {
+        let mut version = version.clone();
+        version.build = semver::BuildMetadata::EMPTY;
+        format!("{}{}", operator, version)
+    } else if comparator.minor.is_some() {
+        format!("{}{}.{}", operator, version.major, version.minor)
+    } else {
+        format!("{}{}", operator, version.major)
+    };
+    Some(new_requirement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write;
+
+    #[test]
+    fn test_updated_requirement_keeps_operator_and_precision() {
+        let version = semver::Version::parse("0.2.1").unwrap();
+        let cases = [
+            ("0.1", Some("0.2")),
+            ("^0.1.0", Some("^0.2.1")),
+            ("~0.1", Some("~0.2")),
+            ("=0.1.0", Some("=0.2.1")),
+            ("0", None),
+            ("~0", None),
+            ("=0.1", Some("=0.2")),
+            ("0.2", None),
+            (">=0.1, <0.2", None),
+            ("*", None),
+        ];
+
+        for (requirement, expected) in cases {
+            assert_eq!(
+                updated_requirement(requirement, &version).as_deref(),
+                expected,
+                "{}",
+                requirement
+            );
+        }
+    }
+
+    #[test]
+    fn test_updated_requirement_spells_out_pre_release() {
+        let version = semver::Version::parse("1.0.0-beta.1+abc").unwrap();
+
+        assert_eq!(
+            updated_requirement("^0.9", &version).as_deref(),
+            Some("^1.0.0-beta.1")
+        );
+    }
+
+    #[test]
+    fn test_sync_requirements() {
+        let root = tempfile::tempdir().unwrap();
+        write(
+            root.path(),
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.dependencies]\ncore = { path = \"crates/core\", version = \"=0.1.0\" }\n",
+        );
+        write(
+            root.path(),
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"core\"\nversion = \"0.2.0\"\n",
+        );
+        write(
+            root.path(),
+            "crates/app/Cargo.toml",
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n\n[dependencies]\n# Keep in step with core\ncore = { path = \"../core\", version = \"0.1\" }  # path dep\nserde = \"1\"\n\n[dev-dependencies.core]\npath = \"../core\"\nversion = \"~0.2\"\n",
+        );
+        let members = ["crates/app", "crates/core"].map(PathBuf::from);
+
+        let changes = sync_requirements(root.path(), &members).unwrap();
+
+        let changes = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.path.strip_prefix(root.path()).unwrap().to_path_buf(),
+                    change.previous_requirement.as_str(),
+                    change.new_requirement.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (PathBuf::from("Cargo.toml"), "=0.1.0", "=0.2.0"),
+                (PathBuf::from("crates/app/Cargo.toml"), "0.1", "0.2"),
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(root.path().join("crates/app/Cargo.toml")).unwrap(),
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n\n[dependencies]\n# Keep in step with core\ncore = { path = \"../core\", version = \"0.2\" }  # path dep\nserde = \"1\"\n\n[dev-dependencies.core]\npath = \"../core\"\nversion = \"~0.2\"\n"
+        );
+        assert!(sync_requirements(root.path(), &members).unwrap().is_empty());
+    }
+}
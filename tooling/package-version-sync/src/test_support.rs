@@ -0,0 +1,28 @@
+//! Fixture helpers shared by the unit tests.
+
+use std::fs;
+use std::path::Path;
+
+/// Writes `contents` to `path`, relative to `root`, creating any missing parent
+/// directories.
+pub(crate) fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// A crate's Cargo.toml carrying `version`.
+pub(crate) fn create_cargo_toml(version: &str) -> String {
+    format!(
+        "[package]\nname = \"test-package\"\nversion = \"{}\"\n",
+        version
+    )
+}
+
+/// A package.json carrying `version`.
+pub(crate) fn create_package_json(version: &str) -> String {
+    format!(
+        "{{\n  \"name\": \"test-package\",\n  \"version\": \"{}\"\n}}\n",
+        version
+    )
+}
@@ -0,0 +1,417 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::discovery::pnpm_package_dirs;
+use crate::json::Node;
+use crate::{Error, Transaction, json};
+
+/// package.json fields whose entries can use the `workspace:` protocol.
+const DEPENDENCY_FIELDS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// A package.json whose `workspace:` ranges were resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedManifest {
+    /// The workspace package.json that was read
+    pub source: PathBuf,
+    /// Where the resolved package.json was written
+    pub output: PathBuf,
+    /// Every `workspace:` range that was replaced, in file order
+    pub ranges: Vec<ResolvedRange>,
+}
+
+/// One dependency whose `workspace:` range was replaced with a concrete one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedRange {
+    /// Key of the dependency, as written in package.json
+    pub dependency: String,
+    /// The range before, such as `workspace:^`
+    pub previous_range: String,
+    /// The range after, such as `^1.2.0`
+    pub new_range: String,
+}
+
+/// A package of the pnpm workspace, as a `workspace:` range can refer to it.
+struct WorkspacePackageJson {
+    path: PathBuf,
+    contents: String,
+    dir: PathBuf,
+    name: Option<String>,
+    version: Option<String>,
+}
+
+/// Replaces the `workspace:` ranges in the package.json files of the pnpm workspace
+/// at `root` with the concrete ranges pnpm writes when publishing.
+///
+/// The workspace is the root package.json plus every package matched by
+/// `root/pnpm-workspace.yaml`. In the `dependencies`, `devDependencies`,
+/// `peerDependencies` and `optionalDependencies` of each:
+///
+/// * `workspace:*` becomes the target's version, `workspace:^` and `workspace:~`
+///   that version behind `^` or `~`
+/// * `workspace:<range>`, such as `workspace:^1.2.0`, becomes `<range>`
+/// * `workspace:<name>@<spec>` becomes `npm:<name>@<range>`, resolving `<spec>` as
+///   above against the package called `<name>`
+/// * `workspace:<path>` becomes the version of the package at that relative path
+///
+/// With `output_dir`, every package.json of the workspace is written under it at
+/// the same relative path, resolved or not, and the originals are left alone.
+/// Otherwise only the files that changed are rewritten in place. Either way the
+/// writes land together through one `Transaction`. The returned list holds the
+/// files that had a `workspace:` range, sorted by path.
+///
+/// # Errors
+///
+/// Errors about a package.json are tied to it through `Error::InFile`.
+///
+/// * `Error::UnknownWorkspacePackage` - A range names a package that is not in the
+///   workspace
+/// * `Error::MissingPackageJsonVersion` - A range's target package has no version
+/// * `Error::Io` - A file cannot be read or written
+/// * `Error::JsonSyntax` / `Error::Serde` - Invalid package.json
+/// * The errors of `discover_packages` for pnpm-workspace.yaml, and of
+///   `Transaction::commit`
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use package_version_sync::resolve_workspace_ranges;
+///
+/// for manifest in resolve_workspace_ranges(".".as_ref(), Some("out".as_ref()))? {
+///     for range in &manifest.ranges {
+///         println!("{}: {} -> {}", range.dependency, range.previous_range, range.new_range);
+///     }
+/// }
+/// # Ok::<(), package_version_sync::Error>(())
+/// ```
+pub fn resolve_workspace_ranges(
+    root: &Path,
+    output_dir: Option<&Path>,
+) -> Result<Vec<ResolvedManifest>, Error> {
+    let mut packages = Vec::new();
+    for dir in pnpm_package_dirs(root)? {
+        let path = root.join(&dir).join("package.json");
+        packages.push(WorkspacePackageJson::read(path, dir)?);
+    }
+
+    let mut resolved = Vec::new();
+    let mut transaction = Transaction::new();
+    for package in &packages {
+        let (contents, ranges) = package.resolve(&packages)?;
+        let output = match output_dir {
+            Some(output_dir) => output_dir.join(&package.dir).join("package.json"),
+            None if ranges.is_empty() => continue,
+            None => package.path.clone(),
+        };
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent).map_err(|error| Error::Io(error).in_file(&output, None))?;
+        }
+        transaction.stage(&output, contents);
+        if !ranges.is_empty() {
+            resolved.push(ResolvedManifest {
+                source: package.path.clone(),
+                output,
+                ranges,
+            });
+        }
+    }
+    transaction.commit()?;
+
+    Ok(resolved)
+}
+
+impl WorkspacePackageJson {
+    fn read(path: PathBuf, dir: PathBuf) -> Result<Self, Error> {
+        let contents =
+            fs::read_to_string(&path).map_err(|error| Error::Io(error).in_file(&path, None))?;
+        let parsed = serde_json::from_str::<serde_json::Value>(&contents)
+            .map_err(|error| Error::Serde(error).in_file(&path, Some(&contents)))?;
+        let field = |key| {
+            parsed
+                .get(key)
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+        };
+        let name = field("name");
+        let version = field("version");
+        Ok(Self {
+            path,
+            contents,
+            dir,
+            name,
+            version,
+        })
+    }
+
+    /// The contents with every `workspace:` range replaced, and the replacements.
+    fn resolve(&self, packages: &[Self]) -> Result<(String, Vec<ResolvedRange>), Error> {
+        let root = json::parse(&self.contents)
+            .map_err(|error| Error::JsonSyntax(error).in_file(&self.path, Some(&self.contents)))?;
+
+        let mut edits: Vec<(Range<usize>, ResolvedRange)> = Vec::new();
+        for field in DEPENDENCY_FIELDS {
+            let Some(json::NodeKind::Object(members)) = root.get(field).map(|node| &node.kind)
+            else {
+                continue;
+            };
+            for member in members {
+                let Some(spec) = member
+                    .value
+                    .as_str()
+                    .and_then(|range| range.strip_prefix("workspace:"))
+                else {
+                    continue;
+                };
+                let new_range = self
+                    .resolve_spec(&member.key, spec, packages)
+                    .map_err(|error| self.locate(error, &member.value))?;
+                edits.push((
+                    member.value.span.clone(),
+                    ResolvedRange {
+                        dependency: member.key.clone(),
+                        previous_range: format!("workspace:{}", spec),
+                        new_range,
+                    },
+                ));
+            }
+        }
+
+        edits.sort_by_key(|(span, _)| span.start);
+        let contents = json::splice(
+            &self.contents,
+            edits
+                .iter()
+                .map(|(span, range)| (span.clone(), json::quote(&range.new_range))),
+        );
+        serde_json::from_str::<serde_json::Value>(&contents)
+            .map_err(|error| Error::Serde(error).in_file(&self.path, Some(&contents)))?;
+
+        Ok((
+            contents,
+            edits.into_iter().map(|(_, range)| range).collect(),
+        ))
+    }
+
+    /// Resolves what follows `workspace:` in the range of `dependency`.
+    fn resolve_spec(
+        &self,
+        dependency: &str,
+        spec: &str,
+        packages: &[Self],
+    ) -> Result<String, Error> {
+        if spec.starts_with('.') || spec.starts_with('/') {
+            let target_dir = fs::canonicalize(self.package_dir().join(spec)).map_err(|_| {
+                Error::UnknownWorkspacePackage {
+                    name: spec.to_string(),
+                }
+            })?;
+            let target = packages
+                .iter()
+                .find(|package| {
+                    fs::canonicalize(package.package_dir()).is_ok_and(|dir| dir == target_dir)
+                })
+                .ok_or_else(|| Error::UnknownWorkspacePackage {
+                    name: spec.to_string(),
+                })?;
+            return target.version();
+        }
+
+        // `@` past the first character splits an alias from its spec, leaving a
+        // scope's leading `@` alone.
+        if let Some(at) = spec
+            .get(1..)
+            .and_then(|rest| rest.rfind('@'))
+            .map(|at| at + 1)
+        {
+            let (name, spec) = (&spec[..at], &spec[at + 1..]);
+            let range = resolve_named(name, spec, packages)?;
+            return Ok(format!("npm:{}@{}", name, range));
+        }
+
+        resolve_named(dependency, spec, packages)
+    }
+
+    fn package_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    fn version(&self) -> Result<String, Error> {
+        self.version.clone().ok_or_else(|| {
+            Error::MissingPackageJsonVersion.in_file(&self.path, Some(&self.contents))
+        })
+    }
+
+    fn locate(&self, error: Error, node: &Node) -> Error {
+        match error {
+            Error::InFile { .. } => error,
+            error => error.at(&self.path, Some(&self.contents), Some(node.span.clone())),
+        }
+    }
+}
+
+/// Resolves `spec` against the workspace package called `name`.
+fn resolve_named(
+    name: &str,
+    spec: &str,
+    packages: &[WorkspacePackageJson],
+) -> Result<String, Error> {
+    let target = || {
+        packages
+            .iter()
+            .find(|package| package.name.as_deref() == Some(name))
+            .ok_or_else(|| Error::UnknownWorkspacePackage {
+                name: name.to_string(),
+            })
+    };
+    match spec {
+        "*" | "" => target()?.version(),
+        "^" | "~" => Ok(format!("{}{}", spec, target()?.version()?)),
+        range => {
+            target()?;
+            Ok(range.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::write;
+
+    const WEB_PACKAGE_JSON: &str = r#"{
+  "name": "web",
+  "version": "0.3.2",
+  "dependencies": {
+    "net-utils": "workspace:^",
+    "next": "16.0.1",
+    "utils-exact": "workspace:*",
+    "utils-tilde": "workspace:~",
+    "utils-range": "workspace:^0.0.1",
+    "utils-alias": "workspace:net-utils@*",
+    "utils-path": "workspace:../../packages/net-utils"
+  }
+}
+"#;
+
+    fn write_pnpm_workspace(root: &Path) {
+        write(
+            root,
+            "pnpm-workspace.yaml",
+            "packages:\n  - apps/*\n  - packages/*\n",
+        );
+    }
+
+    fn write_package(root: &Path, name: &str) {
+        write(
+            root,
+            &format!("packages/{}/package.json", name),
+            &format!("{{ \"name\": \"{}\", \"version\": \"1.2.0\" }}\n", name),
+        );
+    }
+
+    #[test]
+    fn test_resolve_workspace_ranges_in_place() {
+        let root = tempfile::tempdir().unwrap();
+        write_pnpm_workspace(root.path());
+        write(root.path(), "apps/web/package.json", WEB_PACKAGE_JSON);
+        for name in ["net-utils", "utils-exact", "utils-tilde", "utils-range"] {
+            write_package(root.path(), name);
+        }
+
+        let resolved = resolve_workspace_ranges(root.path(), None).unwrap();
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(
+            resolved[0].output,
+            root.path().join("apps/web/package.json")
+        );
+        let ranges = resolved[0]
+            .ranges
+            .iter()
+            .map(|range| (range.dependency.as_str(), range.new_range.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranges,
+            [
+                ("net-utils", "^1.2.0"),
+                ("utils-exact", "1.2.0"),
+                ("utils-tilde", "~1.2.0"),
+                ("utils-range", "^0.0.1"),
+                ("utils-alias", "npm:net-utils@1.2.0"),
+                ("utils-path", "1.2.0"),
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(root.path().join("apps/web/package.json")).unwrap(),
+            WEB_PACKAGE_JSON
+                .replace("workspace:^\"", "^1.2.0\"")
+                .replace("workspace:*", "1.2.0")
+                .replace("workspace:~", "~1.2.0")
+                .replace("workspace:^0.0.1", "^0.0.1")
+                .replace("workspace:net-utils@*", "npm:net-utils@1.2.0")
+                .replace("workspace:../../packages/net-utils", "1.2.0")
+        );
+    }
+
+    #[test]
+    fn test_resolve_workspace_ranges_to_output_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let web_package_json = "{ \"dependencies\": { \"net-utils\": \"workspace:^\" } }\n";
+        write_pnpm_workspace(root.path());
+        write(root.path(), "package.json", "{ \"private\": true }\n");
+        write(root.path(), "apps/web/package.json", web_package_json);
+        write_package(root.path(), "net-utils");
+        let output = tempfile::tempdir().unwrap();
+
+        let resolved = resolve_workspace_ranges(root.path(), Some(output.path())).unwrap();
+
+        assert_eq!(
+            resolved[0].output,
+            output.path().join("apps/web/package.json")
+        );
+        assert_eq!(
+            fs::read_to_string(root.path().join("apps/web/package.json")).unwrap(),
+            web_package_json
+        );
+        assert_eq!(
+            fs::read_to_string(output.path().join("apps/web/package.json")).unwrap(),
+            "{ \"dependencies\": { \"net-utils\": \"^1.2.0\" } }\n"
+        );
+        assert_eq!(
+            fs::read_to_string(output.path().join("packages/net-utils/package.json")).unwrap(),
+            "{ \"name\": \"net-utils\", \"version\": \"1.2.0\" }\n"
+        );
+        assert!(output.path().join("package.json").is_file());
+    }
+
+    #[test]
+    fn test_resolve_workspace_ranges_unknown_package() {
+        let root = tempfile::tempdir().unwrap();
+        write_pnpm_workspace(root.path());
+        write(
+            root.path(),
+            "apps/web/package.json",
+            "{ \"dependencies\": { \"missing\": \"workspace:*\" } }",
+        );
+
+        let error = resolve_workspace_ranges(root.path(), None).unwrap_err();
+
+        let Error::InFile { span, source, .. } = error else {
+            panic!("expected an error tied to the file, got {:?}", error);
+        };
+        assert_eq!(span, Some(31..44));
+        assert!(
+            matches!(*source, Error::UnknownWorkspacePackage { ref name } if name == "missing")
+        );
+        assert!(
+            fs::read_to_string(root.path().join("apps/web/package.json"))
+                .unwrap()
+                .contains("workspace:*")
+        );
+    }
+}
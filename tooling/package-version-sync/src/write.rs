@@ -0,0 +1,348 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::Error;
+use crate::manifest::Manifest;
+
+/// A set of file writes that land together or not at all.
+///
+/// Writes are staged in memory and only touch the disk on `commit`, which writes
+/// each file atomically. If one write fails, the files already written are put
+/// back from a journal of their original contents, so a run never leaves some
+/// manifests updated and others not.
+#[derive(Clone, Debug, Default)]
+pub struct Transaction {
+    writes: Vec<(PathBuf, String)>,
+}
+
+impl Transaction {
+    /// Starts an empty transaction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stages `contents` to be written to `path`, replacing any earlier write staged
+    /// for the same path.
+    pub fn stage(&mut self, path: impl Into<PathBuf>, contents: impl Into<String>) {
+        let path = path.into();
+        let contents = contents.into();
+        match self.writes.iter_mut().find(|(staged, _)| *staged == path) {
+            Some(write) => write.1 = contents,
+            None => self.writes.push((path, contents)),
+        }
+    }
+
+    /// Stages the current contents of a manifest to be written to its path.
+    pub fn stage_manifest(&mut self, manifest: &dyn Manifest) {
+        self.stage(manifest.path(), manifest.contents());
+    }
+
+    /// Paths staged so far, in the order they will be written.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.writes.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Writes every staged file, in staging order.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::WriteFailed` - A file could not be written; every file written
+    ///   before it was restored, and so was the file itself when the failure came
+    ///   after it had been replaced
+    /// * `Error::RollbackFailed` - A file could not be written, and restoring some of
+    ///   the files written before it failed too
+    pub fn commit(self) -> Result<(), Error> {
+        let mut journal: Vec<(&Path, Option<Vec<u8>>)> = Vec::new();
+        for (path, contents) in &self.writes {
+            let result = read_original(path).and_then(|original| {
+                journal.push((path, original));
+                write_atomic_io(path, contents.as_bytes())
+            });
+            if let Err(source) = result {
+                // The failing file is in the journal too: flushing the directory can
+                // fail after the rename has already replaced it.
+                let unrestored = roll_back(&journal);
+                let path = path.clone();
+                return Err(if unrestored.is_empty() {
+                    Error::WriteFailed { path, source }
+                } else {
+                    Error::RollbackFailed {
+                        path,
+                        source,
+                        unrestored,
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_original(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Restores every journaled file, latest first, returning those that could not be.
+fn roll_back(journal: &[(&Path, Option<Vec<u8>>)]) -> Vec<PathBuf> {
+    journal
+        .iter()
+        .rev()
+        .filter(|(path, original)| restore(path, original.as_deref()).is_err())
+        .map(|(path, _)| path.to_path_buf())
+        .collect()
+}
+
+/// Puts a file back the way the journal found it, removing it if it did not exist.
+/// A file still in that state is left alone.
+fn restore(path: &Path, original: Option<&[u8]>) -> io::Result<()> {
+    if read_original(path)?.as_deref() == original {
+        return Ok(());
+    }
+    match original {
+        Some(contents) => write_atomic_io(path, contents),
+        None => fs::remove_file(path),
+    }
+}
+
+/// Replaces the file at `path` with `contents` so that readers only ever see the old
+/// or the new contents, never a truncated mix.
+///
+/// The contents go to a temporary file in the same directory, which is flushed to
+/// disk and then renamed over the target. An existing file keeps its permissions,
+/// and a symlink keeps pointing at the file it links to, which is what gets
+/// replaced.
+///
+/// # Errors
+///
+/// Returns `Error::Io` when any step fails; the temporary file is removed and the
+/// original left untouched.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> Result<(), Error> {
+    write_atomic_io(path, contents.as_bytes()).map_err(Error::Io)
+}
+
+fn write_atomic_io(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let (path, permissions) = match fs::canonicalize(path) {
+        Ok(target) => {
+            let permissions = fs::metadata(&target)?.permissions();
+            (target, Some(permissions))
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => (path.to_path_buf(), None),
+        Err(error) => return Err(error),
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a file path", path.display()),
+        )
+    })?;
+    let temp_path = dir.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+        file.write_all(contents)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.sync_all()?;
+        fs::rename(&temp_path, &path)?;
+        sync_dir(&dir)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Flushes the directory entry of a rename to disk.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir_entries(dir: &Path) -> Vec<String> {
+        let mut entries = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("package.json");
+        fs::write(&path, "{ \"version\": \"1.0.0\" }").unwrap();
+
+        write_atomic(&path, "{ \"version\": \"1.1.0\" }").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{ \"version\": \"1.1.0\" }"
+        );
+        assert_eq!(dir_entries(dir.path()), ["package.json"]);
+    }
+
+    #[test]
+    fn test_write_atomic_creates_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+
+        write_atomic(&path, "[package]\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[package]\n");
+    }
+
+    #[test]
+    fn test_write_atomic_leaves_original_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory cannot be replaced by renaming a file over it.
+        let path = dir.path().join("Cargo.toml");
+        fs::create_dir(&path).unwrap();
+
+        let result = write_atomic(&path, "[package]\n");
+
+        assert!(matches!(result, Err(Error::Io(_))));
+        assert!(path.is_dir());
+        assert_eq!(dir_entries(dir.path()), ["Cargo.toml"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_preserves_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_atomic(&path, "[package]\nversion = \"1.0.0\"\n").unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_follows_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("real.json");
+        let link = dir.path().join("package.json");
+        fs::write(&target, "{}").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_atomic(&link, "{ \"version\": \"1.0.0\" }").unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(
+            fs::read_to_string(&target).unwrap(),
+            "{ \"version\": \"1.0.0\" }"
+        );
+    }
+
+    #[test]
+    fn test_transaction_commits_all_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let cargo_toml = dir.path().join("Cargo.toml");
+        let package_json = dir.path().join("package.json");
+        fs::write(&cargo_toml, "[package]\nversion = \"1.0.0\"\n").unwrap();
+
+        let mut transaction = Transaction::new();
+        transaction.stage(&cargo_toml, "[package]\nversion = \"1.1.0\"\n");
+        transaction.stage(&package_json, "{ \"version\": \"1.0.0\" }");
+        transaction.stage(&package_json, "{ \"version\": \"1.1.0\" }");
+        transaction.commit().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&cargo_toml).unwrap(),
+            "[package]\nversion = \"1.1.0\"\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&package_json).unwrap(),
+            "{ \"version\": \"1.1.0\" }"
+        );
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let cargo_toml = dir.path().join("Cargo.toml");
+        let package_json = dir.path().join("package.json");
+        let cargo_lock = dir.path().join("Cargo.lock");
+        fs::write(&cargo_toml, "[package]\nversion = \"1.0.0\"\n").unwrap();
+        // A directory cannot be replaced by a file, so the last write fails.
+        fs::create_dir(&cargo_lock).unwrap();
+
+        let mut transaction = Transaction::new();
+        transaction.stage(&cargo_toml, "[package]\nversion = \"1.1.0\"\n");
+        transaction.stage(&package_json, "{ \"version\": \"1.1.0\" }");
+        transaction.stage(&cargo_lock, "version = 4\n");
+        let result = transaction.commit();
+
+        match result.unwrap_err() {
+            Error::WriteFailed { path, .. } => assert_eq!(path, cargo_lock),
+            error => panic!("Expected WriteFailed error, got {:?}", error),
+        }
+        assert_eq!(
+            fs::read_to_string(&cargo_toml).unwrap(),
+            "[package]\nversion = \"1.0.0\"\n"
+        );
+        assert!(!package_json.exists());
+        assert_eq!(dir_entries(dir.path()), ["Cargo.lock", "Cargo.toml"]);
+    }
+
+    #[test]
+    fn test_roll_back_restores_replaced_and_untouched_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let cargo_toml = dir.path().join("Cargo.toml");
+        let package_json = dir.path().join("package.json");
+        let cargo_lock = dir.path().join("Cargo.lock");
+        // Replaced before the failure, as when only flushing the directory failed.
+        fs::write(&cargo_toml, "[package]\nversion = \"1.1.0\"\n").unwrap();
+        fs::write(&package_json, "{ \"version\": \"1.1.0\" }").unwrap();
+        let journal = [
+            (
+                cargo_toml.as_path(),
+                Some(b"[package]\nversion = \"1.0.0\"\n".to_vec()),
+            ),
+            (package_json.as_path(), None),
+            // Failed before it was ever created.
+            (cargo_lock.as_path(), None),
+        ];
+
+        let unrestored = roll_back(&journal);
+
+        assert!(unrestored.is_empty());
+        assert_eq!(
+            fs::read_to_string(&cargo_toml).unwrap(),
+            "[package]\nversion = \"1.0.0\"\n"
+        );
+        assert_eq!(dir_entries(dir.path()), ["Cargo.toml"]);
+    }
+}